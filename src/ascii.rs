@@ -0,0 +1,72 @@
+use crate::board::{board_lines, info_lines, inventory_line};
+use crate::levels::LevelInfo;
+use crate::plugins::touch::TouchControls;
+use bevy::asset::AssetServerSettings;
+use bevy::prelude::*;
+
+/// Board written as text into `#board`, `#inventory` and `#info` elements of the page
+/// (`ascii/index.html`) instead of being rendered with WebGL2. Readable by screen readers.
+/// Winit still provides the (empty) canvas that keyboard input comes from.
+pub struct AsciiPlugin;
+
+impl Plugin for AsciiPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // the page is in `ascii/`, next to `assets/`
+        app.add_resource(AssetServerSettings {
+            asset_folder: "../assets".to_string(),
+        })
+        .add_resource(WindowDescriptor {
+            title: "Robbo".to_string(),
+            ..Default::default()
+        })
+        .add_plugin(bevy::reflect::ReflectPlugin)
+        .add_plugin(bevy::core::CorePlugin)
+        .add_plugin(bevy::asset::AssetPlugin)
+        .add_plugin(bevy::input::InputPlugin)
+        .add_plugin(bevy::window::WindowPlugin::default())
+        .add_plugin(bevy::winit::WinitPlugin::default())
+        // no on-screen controls, but keyboard systems read them
+        .add_resource(TouchControls::default())
+        .init_resource::<AsciiScreen>()
+        .add_system_to_stage(stage::LAST, ascii_render_system.system());
+        #[cfg(feature = "audio")]
+        app.add_plugin(bevy::audio::AudioPlugin);
+    }
+}
+
+/// texts of the elements, written into the page only when they change
+#[derive(Default)]
+pub struct AsciiScreen {
+    board: String,
+    inventory: String,
+    info: String,
+}
+
+/// Board in level file notation (empty cells as spaces), inventory and game state prompt
+pub fn ascii_render_system(world: &mut World, resources: &mut Resources) {
+    let board: Vec<_> = board_lines(world, &resources.get::<LevelInfo>().unwrap())
+        .iter()
+        .map(|line| line.replace('.', " "))
+        .collect();
+    let inventory = inventory_line(resources);
+    let info = info_lines(resources).join("\n");
+
+    let mut screen = resources.get_mut::<AsciiScreen>().unwrap();
+    set_text("board", &mut screen.board, board.join("\n"));
+    set_text("inventory", &mut screen.inventory, inventory);
+    set_text("info", &mut screen.info, info);
+}
+
+fn set_text(id: &str, shown: &mut String, text: String) {
+    if *shown == text {
+        return;
+    }
+    let element = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(id));
+    match element {
+        Some(element) => element.set_text_content(Some(&text)),
+        None => warn!("no #{} element on the page", id),
+    }
+    *shown = text;
+}
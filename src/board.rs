@@ -0,0 +1,165 @@
+use crate::components::prelude::*;
+use crate::inventory::Inventory;
+use crate::levels::{LevelInfo, LevelSet};
+use crate::plugins::controls::ControlsEditor;
+use crate::plugins::GameState;
+use bevy::prelude::*;
+
+static WALL_TILES: &[(u32, char)] = &[
+    (2, 'O'),
+    (29, 'o'),
+    (19, '-'),
+    (3, 'Q'),
+    (21, 'q'),
+    (68, 'p'),
+    (69, 'P'),
+    (10, 's'),
+    (22, 'S'),
+];
+
+/// Symbol of the entity in level [data] notation. Things that never appear
+/// in level files are shown as ':' (bullets, lasers, blasters) and ',' (animations).
+pub fn board_symbol(world: &World, entity: Entity) -> char {
+    if world.get::<Wall>(entity).is_ok() {
+        let tile = world.get::<Tiles>(entity).map(|t| t.tiles()[0]).unwrap_or(0);
+        return WALL_TILES
+            .iter()
+            .find(|(t, _)| *t == tile)
+            .map(|(_, c)| *c)
+            .unwrap_or('O');
+    }
+    if let Ok(bear) = world.get::<Bear>(entity) {
+        return if bear.0 { '*' } else { '@' };
+    }
+    if let Ok(collectable) = world.get::<Collectable>(entity) {
+        return match *collectable {
+            Collectable::Key => '%',
+            Collectable::Screw => 'T',
+            Collectable::Ammo => '\'',
+            Collectable::Life => '+',
+        };
+    }
+    if let Ok(usable) = world.get::<Usable>(entity) {
+        return match *usable {
+            Usable::Door => 'D',
+            Usable::Teleport => '&',
+            Usable::Capsule => '!',
+        };
+    }
+    if world.get::<Robbo>(entity).is_ok() {
+        'R'
+    } else if world.get::<Bird>(entity).is_ok() {
+        '^'
+    } else if world.get::<Eyes>(entity).is_ok() {
+        'V'
+    } else if world.get::<PushBox>(entity).is_ok() {
+        '~'
+    } else if world.get::<Capsule>(entity).is_ok() {
+        '!'
+    } else if world.get::<Bomb>(entity).is_ok() {
+        'b'
+    } else if world.get::<Bomb2>(entity).is_ok() {
+        'B'
+    } else if world.get::<Stop>(entity).is_ok() {
+        'X'
+    } else if world.get::<RadioactiveField>(entity).is_ok() {
+        'k'
+    } else if world.get::<QuestionMark>(entity).is_ok() {
+        '?'
+    } else if world.get::<ForceField>(entity).is_ok() {
+        '='
+    } else if world.get::<Magnet>(entity).is_ok() {
+        'M'
+    } else if world.get::<Gun>(entity).is_ok() {
+        '}'
+    } else if world.get::<Moveable>(entity).is_ok() {
+        '#'
+    } else if world.get::<Animation>(entity).is_ok() {
+        ','
+    } else if world.get::<Bullet>(entity).is_ok()
+        || world.get::<LaserHead>(entity).is_ok()
+        || world.get::<LaserTail>(entity).is_ok()
+        || world.get::<BlasterHead>(entity).is_ok()
+    {
+        ':'
+    } else if world.get::<Destroyable>(entity).is_ok() {
+        'H'
+    } else {
+        '.'
+    }
+}
+
+/// Current board as rows of level [data] symbols, in level file orientation
+pub fn board_rows(world: &World, level_info: &LevelInfo) -> Vec<String> {
+    let (width, height) = (level_info.width.max(0), level_info.height.max(0));
+    let mut rows = vec![vec!['.'; height as usize]; width as usize];
+    for (entity, pos) in world.query::<(Entity, &Position)>() {
+        let (x, y) = pos.as_tuple();
+        if x < 0 || y < 0 || x >= width || y >= height {
+            continue;
+        }
+        let cell = &mut rows[x as usize][y as usize];
+        let symbol = board_symbol(world, entity);
+        // animations (explosions etc.) never hide what they are played over
+        if *cell == '.' || (*cell == ',' && symbol != '.') {
+            *cell = symbol;
+        }
+    }
+    rows.into_iter().map(|row| row.into_iter().collect()).collect()
+}
+
+/// Board as seen on screen (transposed `board_rows`): one line per row, top row first
+pub fn board_lines(world: &World, level_info: &LevelInfo) -> Vec<String> {
+    let columns: Vec<Vec<char>> = board_rows(world, level_info)
+        .iter()
+        .map(|column| column.chars().collect())
+        .collect();
+    (0..level_info.height.max(0) as usize)
+        .rev()
+        .map(|y| columns.iter().map(|column| column[y]).collect())
+        .collect()
+}
+
+/// Inventory of text frontends (the status bar of the window)
+pub fn inventory_line(resources: &Resources) -> String {
+    let level_info = resources.get::<LevelInfo>().unwrap();
+    let inventory = resources.get::<Inventory>().unwrap();
+    let screws_left = level_info.screws.saturating_sub(inventory.screws);
+    let mut line = format!(
+        "screws {}  keys {}  ammo {}",
+        screws_left, inventory.keys, inventory.bullets
+    );
+    if let Some(lives) = inventory.lives {
+        line += &format!("  lives {}", lives);
+    }
+    line
+}
+
+/// Level being played and what the player is asked to do (shown in the window title)
+pub fn info_lines(resources: &Resources) -> Vec<String> {
+    let level_info = resources.get::<LevelInfo>().unwrap();
+    let level_sets = resources.get::<Assets<LevelSet>>().unwrap();
+    let level_set = level_sets.get(&level_info.level_set_handle);
+    let level = level_set.and_then(|level_set| level_set.get(level_info.current_level));
+    let mut line = String::new();
+    if let Some(name) = level_set.and_then(|level_set| level_set.name.as_ref()) {
+        line += &format!("{} - ", name);
+    }
+    line += &format!(
+        "level {}",
+        level.map_or(level_info.current_level + 1, |level| level.number)
+    );
+    if let Some(author) = level.and_then(|level| level.author.as_ref()) {
+        line += &format!(" by {}", author);
+    }
+    let mut lines = vec![line];
+    lines.extend(level.iter().flat_map(|level| level.notes.iter().cloned()));
+    let editor_prompt = resources
+        .get::<ControlsEditor>()
+        .and_then(|editor| editor.prompt.clone());
+    let state_prompt = resources
+        .get::<State<GameState>>()
+        .and_then(|state| state.current().prompt().map(String::from));
+    lines.extend(editor_prompt.or(state_prompt));
+    lines
+}
@@ -1,29 +1,58 @@
 use crate::game_events::GameEvent;
+#[derive(Clone, Copy)]
 pub struct Robbo;
+#[derive(Clone, Copy)]
 pub struct Bomb(pub bool);
+#[derive(Clone, Copy)]
 pub struct Bird;
+#[derive(Clone, Copy)]
 pub struct MovingBetweenWalls;
+#[derive(Clone, Copy)]
 pub struct Bear(pub bool);
+#[derive(Clone, Copy)]
 pub struct Eyes;
+#[derive(Clone, Copy)]
 pub struct Bullet;
+#[derive(Clone, Copy)]
 pub struct Wall;
+#[derive(Clone, Copy)]
 pub struct PushBox;
+#[derive(Clone, Copy)]
 pub struct QuestionMark;
+/// Millenium objects
+#[derive(Clone, Copy)]
+pub struct Stop;
+#[derive(Clone, Copy)]
+pub struct RadioactiveField;
+/// Alex object: explodes, then shoots in all directions
+#[derive(Clone, Copy)]
+pub struct Bomb2;
 
+#[derive(Clone, Copy)]
 pub struct Animation(pub Option<GameEvent>);
+#[derive(Clone, Copy)]
 pub struct ForceField(pub MovingDir);
+#[derive(Clone, Copy)]
 pub struct ForceFieldBounds(pub i32, pub i32);
+#[derive(Clone, Copy)]
 pub struct Moveable;
+#[derive(Clone, Copy)]
 pub struct Destroyable;
+#[derive(Clone, Copy)]
 pub struct Deadly;
+#[derive(Clone, Copy)]
 pub enum Usable {
     Door,
     Teleport,
     Capsule,
 }
+#[derive(Clone, Copy)]
 pub struct LaserTail;
+#[derive(Clone, Copy)]
 pub struct RoughUpdate;
+#[derive(Clone, Copy)]
 pub struct Capsule;
+#[derive(Clone, Copy)]
 pub struct Undestroyable;
 
 // Status bar
@@ -38,11 +67,17 @@ pub struct LevelNumber;
 pub struct AmmoCounter;
 #[derive(Clone, Copy)]
 pub struct KeyCounter;
+#[derive(Clone, Copy)]
+pub struct LivesCounter;
+/// dimmed board and icon shown while not playing
+pub struct Overlay;
 
+#[derive(Clone, Copy)]
 pub struct LaserHead {
     pub gun_pos: Position,
     pub is_moving_back: bool,
 }
+#[derive(Clone, Copy)]
 pub struct BlasterHead;
 
 #[derive(Debug, Clone, Copy)]
@@ -52,8 +87,10 @@ pub enum Collectable {
     Key,
     Screw,
     Ammo,
+    Life,
 }
 
+#[derive(Clone, Copy)]
 pub struct ShootingProp(pub f32);
 impl Default for ShootingProp {
     fn default() -> Self {
@@ -163,6 +200,7 @@ impl Int2Ops for MovingDir {
     }
 }
 
+#[derive(Clone, Copy)]
 pub enum Rotatable {
     Regular,
     Random,
@@ -189,15 +227,36 @@ impl Int2Ops for ShootingDir {
     }
 }
 
+/// longest animation of an entity
+pub const MAX_TILES: usize = 8;
+
+/// Animation frames (tile atlas indices) of an entity, stored inline to stay `Copy`
 #[derive(Copy, Debug, Clone)]
 pub struct Tiles {
-    pub tiles: &'static [u32],
+    frames: [u32; MAX_TILES],
+    len: usize,
     pub current: usize,
 }
 
 impl Tiles {
-    pub fn new(tiles: &'static [u32]) -> Self {
-        Self { tiles, current: 0 }
+    /// panics with more than `MAX_TILES` frames
+    pub fn new(tiles: &[u32]) -> Self {
+        assert!(
+            tiles.len() <= MAX_TILES,
+            "{} animation frames, at most MAX_TILES = {} supported",
+            tiles.len(),
+            MAX_TILES
+        );
+        let mut frames = [0; MAX_TILES];
+        frames[..tiles.len()].copy_from_slice(tiles);
+        Self {
+            frames,
+            len: tiles.len(),
+            current: 0,
+        }
+    }
+    pub fn tiles(&self) -> &[u32] {
+        &self.frames[..self.len]
     }
 }
 
@@ -121,6 +121,10 @@ pub fn create_key<'a>(commands: &'a mut Commands) -> &'a mut Commands {
     commands.spawn((Collectable::Key, Tiles::new(&[7])))
 }
 
+pub fn create_extra_life<'a>(commands: &'a mut Commands) -> &'a mut Commands {
+    commands.spawn((Collectable::Life, Destroyable, Tiles::new(&[34])))
+}
+
 pub fn create_ground<'a>(commands: &'a mut Commands) -> &'a mut Commands {
     commands.spawn((Destroyable, Tiles::new(&[77])))
 }
@@ -138,6 +142,32 @@ pub fn create_bomb<'a>(commands: &'a mut Commands) -> &'a mut Commands {
     commands.spawn((Bomb(false), Moveable, Destroyable, Tiles::new(&[8])))
 }
 
+pub fn create_bomb2<'a>(commands: &'a mut Commands) -> &'a mut Commands {
+    commands.spawn((Bomb2, Moveable, Destroyable, Tiles::new(&[79])))
+}
+
+/// explosion of Bomb2, shoots in all directions when finished
+pub fn create_bomb2_explosion<'a>(commands: &'a mut Commands, pos: Position) -> &'a mut Commands {
+    commands.spawn((
+        Animation(Some(GameEvent::ShootAround(pos))),
+        Undestroyable,
+        Tiles::new(&[84, 85, 86, 85, 84]),
+    ))
+}
+
+pub fn create_stop<'a>(commands: &'a mut Commands) -> &'a mut Commands {
+    commands.spawn((Stop, Undestroyable, Tiles::new(&[76])))
+}
+
+pub fn create_radioactive_field<'a>(commands: &'a mut Commands) -> &'a mut Commands {
+    commands.spawn((
+        RadioactiveField,
+        Deadly,
+        Undestroyable,
+        Tiles::new(&[74, 74, 75, 75]),
+    ))
+}
+
 pub fn create_explosion<'a>(commands: &'a mut Commands) -> &'a mut Commands {
     commands.spawn((
         Animation(None),
@@ -169,7 +199,7 @@ pub fn pre_spawn_robbo<'a>(commands: &'a mut Commands, pos: Position) -> &'a mut
 pub fn fly_away<'a>(commands: &'a mut Commands, pos: Position) -> &'a mut Commands {
     commands
         .spawn((
-            Animation(Some(GameEvent::ReloadLevel(1))),
+            Animation(Some(GameEvent::LevelComplete)),
             Undestroyable,
             Tiles::new(&[17, 18, 17, 18, 17, 18, 17, 18]),
         ))
@@ -254,17 +284,26 @@ pub fn gun_set_shooting_dir(
     )
 }
 
-pub fn _create_horizontal_laser<'a>(
+/// Laser emitter: unmoveable gun firing solid laser beams east (0) or west (2)
+pub fn create_horizontal_laser<'a>(
     commands: &'a mut Commands,
-    _params: &[usize],
+    params: &[usize],
 ) -> &'a mut Commands {
-    commands.spawn((Tiles::new(&[53, 55]),))
+    create_laser(commands, params.first().copied().unwrap_or(0))
 }
-pub fn _create_vertical_laser<'a>(
-    commands: &'a mut Commands,
-    _params: &[usize],
-) -> &'a mut Commands {
-    commands.spawn((Tiles::new(&[54, 56]),))
+
+/// Laser emitter: unmoveable gun firing solid laser beams south (1) or north (3)
+pub fn create_vertical_laser<'a>(commands: &'a mut Commands, params: &[usize]) -> &'a mut Commands {
+    create_laser(commands, params.first().copied().unwrap_or(1))
+}
+
+fn create_laser(commands: &mut Commands, index: usize) -> &mut Commands {
+    commands.spawn((
+        Tiles::new(&GUN_TILES[index..index + 1]),
+        ShootingDir::by_index(index),
+        ShootingProp::default(),
+        Gun::Solid,
+    ))
 }
 
 const MAGNET_TILES: &[u32] = &[73, 0, 72, 1];
@@ -8,5 +8,8 @@ pub enum GameEvent {
     SpawnRobbo(Position),
     PreSpawnRobbo(Position),
     SpawnRandom(Position),
+    ShootAround(Position),
+    /// Robbo flew away with the capsule
+    LevelComplete,
     KillRobbo,
 }
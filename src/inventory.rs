@@ -2,14 +2,26 @@ use crate::components::Collectable;
 use crate::plugins::audio::Sound;
 use bevy::prelude::*;
 
-#[derive(Default, Debug)]
+/// lives at the start of classic game, the attempt being played included
+pub const CLASSIC_LIVES: usize = 8;
+
+#[derive(Default, Debug, Clone)]
 pub struct Inventory {
     pub keys: usize,
     pub screws: usize,
     pub bullets: usize,
+    /// lives left in classic mode, unlimited if `None`; kept when level changes
+    pub lives: Option<usize>,
 }
 
 impl Inventory {
+    pub fn classic() -> Self {
+        Self {
+            lives: Some(CLASSIC_LIVES),
+            ..Default::default()
+        }
+    }
+
     pub fn collect(&mut self, item: Collectable, events: &mut ResMut<Events<Sound>>) {
         match item {
             Collectable::Key => {
@@ -24,6 +36,11 @@ impl Inventory {
                 self.bullets += 9;
                 events.send(Sound::AMMO);
             }
+            Collectable::Life => {
+                self.lives = self.lives.map(|lives| lives + 1);
+                // sound of a new Robbo appearing
+                events.send(Sound::SPAWN);
+            }
         }
     }
 }
@@ -4,6 +4,8 @@ use anyhow;
 use bevy::asset::AssetLoader;
 use bevy::prelude::*;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 use bevy::utils::BoxedFuture;
 use bevy::asset::{LoadedAsset, LoadContext};
 use bevy::reflect::TypeUuid;
@@ -17,6 +19,11 @@ pub struct Level {
     pub tiles: Vec<String>,
     pub screw_count: usize,
     pub additional: AdditionalMap,
+    pub author: Option<String>,
+    /// [level_notes], one entry per line
+    pub notes: Vec<String>,
+    /// [offset] of the level in the original Atari disk image
+    pub offset: Option<String>,
 }
 
 pub struct OccupiedPositions<'a> {
@@ -46,7 +53,7 @@ impl<'a> OccupiedPositions<'a> {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct LevelInfo {
     pub current_level: usize,
     pub level_set_handle: Handle<LevelSet>,
@@ -55,6 +62,8 @@ pub struct LevelInfo {
     pub screws: usize,
     pub missing_robbo_ticks: usize,
     pub wall_positions: HashSet<Position>,
+    /// frame at which current level was (re)built
+    pub start_frame: Option<usize>,
 }
 
 impl LevelInfo {
@@ -87,10 +96,122 @@ impl LevelInfo {
 
 type AdditionalMap = HashMap<(i32, i32), Vec<usize>>;
 
+/// Error in levelset file, `line` is 1-based, `tag` is the section being parsed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LevelError {
+    pub line: usize,
+    pub tag: String,
+    pub message: String,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, {}: {}", self.line, self.tag, self.message)
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+/// Lines of levelset file, tracking line number and the current tag for error reporting
+pub struct LevelLines<'a> {
+    lines: std::str::Lines<'a>,
+    line: usize,
+    tag: &'a str,
+    last_level_line: usize,
+}
+
+impl<'a> LevelLines<'a> {
+    pub fn new(data: &'a str) -> Self {
+        Self {
+            lines: data.lines(),
+            line: 0,
+            tag: "",
+            last_level_line: 0,
+        }
+    }
+    fn next(&mut self) -> Option<&'a str> {
+        let line = self.lines.next()?.trim_end();
+        self.line += 1;
+        if line.starts_with('[') {
+            self.tag = line;
+        }
+        Some(line)
+    }
+    fn peek(&self) -> Option<&'a str> {
+        self.lines.clone().next().map(str::trim_end)
+    }
+    fn error(&self, message: impl Into<String>) -> LevelError {
+        LevelError {
+            line: self.line,
+            tag: self.tag.to_owned(),
+            message: message.into(),
+        }
+    }
+    fn value(&mut self) -> Result<&'a str, LevelError> {
+        match self.lines.clone().next() {
+            Some(line) if !line.starts_with('[') => Ok(self.next().unwrap()),
+            _ => Err(self.error("missing value")),
+        }
+    }
+    /// lines up to the next tag, without trailing empty ones
+    fn text(&mut self) -> Vec<String> {
+        let mut text = vec![];
+        while let Some(line) = self.lines.clone().next() {
+            if line.starts_with('[') {
+                break;
+            }
+            text.push(self.next().unwrap().to_owned());
+        }
+        while let Some("") = text.last().map(String::as_str) {
+            text.pop();
+        }
+        text
+    }
+    fn parse_value<T: FromStr>(&mut self, value: &str) -> Result<T, LevelError> {
+        value
+            .parse()
+            .map_err(|_| self.error(format!("invalid number {:?}", value)))
+    }
+    /// skip the rest of the broken level, up to its [end] or the [level] of the next one
+    fn skip_level(&mut self) {
+        while self.tag != "[end]" && self.peek() != Some("[level]") && self.next().is_some() {}
+    }
+}
+
+static ALL_DIRS: &[usize] = &[0, 1, 2, 3];
+
+/// number of required [additional] params, which of them are directions
+/// and what directions are allowed
+fn additional_params(c: char) -> (usize, &'static [usize], &'static [usize]) {
+    match c {
+        '&' => (2, &[], ALL_DIRS),
+        '^' => (3, &[0, 1], ALL_DIRS),
+        '}' => (4, &[0, 1], ALL_DIRS),
+        '@' | '*' | '=' | 'M' => (1, &[0], ALL_DIRS),
+        'L' => (1, &[0], &[0, 2]),
+        'l' => (1, &[0], &[1, 3]),
+        _ => (0, &[], ALL_DIRS),
+    }
+}
+
 impl Level {
-    pub fn parse(lines: &mut std::str::Split<char>) -> Option<Level> {
-        let mut _level_set_name: Option<&str> = None;
-        let mut default_level_color: Option<String> = Some(String::from("608050"));
+    /// Parses next level, `None` at the end of the levelset.
+    /// Levelset tags met on the way are stored into `level_set`.
+    pub fn parse(
+        lines: &mut LevelLines,
+        level_set: &mut LevelSet,
+    ) -> Option<Result<Level, LevelError>> {
+        let result = Self::try_parse(lines, level_set).transpose();
+        if let Some(Err(_)) = result {
+            lines.skip_level();
+        }
+        result
+    }
+
+    fn try_parse(
+        lines: &mut LevelLines,
+        level_set: &mut LevelSet,
+    ) -> Result<Option<Level>, LevelError> {
         let mut collecting_data: bool = false;
         let mut number: Option<usize> = None;
         let mut width: Option<i32> = None;
@@ -99,64 +220,150 @@ impl Level {
         let mut additional: AdditionalMap = AdditionalMap::new();
         let mut tiles: Vec<String> = vec![];
         let mut screw_count = 0;
+        let mut author: Option<String> = None;
+        let mut notes: Vec<String> = vec![];
+        let mut offset: Option<String> = None;
         loop {
-            let line = lines.next()?;
+            if number.is_some() && lines.peek() == Some("[level]") {
+                // the [level] is left for the next level, which is parsed on its own
+                return Err(LevelError {
+                    line: lines.line + 1,
+                    tag: String::from("[level]"),
+                    message: String::from("previous level has no [end]"),
+                });
+            }
+            let line = match lines.next() {
+                Some(line) => line,
+                None if number.is_some() => {
+                    return Err(lines.error("unexpected end of file, missing [end]"))
+                }
+                None => return Ok(None),
+            };
 
             if line.starts_with('[') {
                 collecting_data = false;
             }
             match line {
-                "[level]" => number = lines.next().unwrap().parse().ok(),
+                "[level]" => {
+                    let value = lines.value()?;
+                    number = Some(lines.parse_value(value)?);
+                }
                 "[name]" => {
-                    _level_set_name = lines.next();
+                    level_set.name = Some(String::from(lines.value()?));
+                }
+                "[notes]" => {
+                    level_set.notes = lines.text();
+                }
+                "[last_level]" => {
+                    let value = lines.value()?;
+                    level_set.last_level = Some(lines.parse_value(value)?);
+                    lines.last_level_line = lines.line;
+                }
+                "[author]" => {
+                    author = Some(String::from(lines.value()?));
+                }
+                "[level_notes]" => {
+                    notes = lines.text();
+                }
+                "[offset]" => {
+                    offset = Some(String::from(lines.value()?));
                 }
                 "[colour]" => {
-                    color = Some(String::from(lines.next().unwrap()));
+                    color = Some(String::from(lines.value()?));
                 }
                 "[default_level_colour]" => {
-                    default_level_color = lines.next().map(|s| s.to_owned())
+                    level_set.default_level_color = String::from(lines.value()?);
                 }
                 "[size]" => {
-                    let mut it = lines
-                        .next()
-                        .unwrap()
-                        .split('.')
-                        .map(|v| v.parse::<i32>().unwrap());
-                    width = it.next();
-                    height = it.next();
+                    let value = lines.value()?;
+                    let mut it = value.split('.');
+                    match (it.next(), it.next(), it.next()) {
+                        (Some(w), Some(h), None) => {
+                            width = Some(lines.parse_value(w)?);
+                            height = Some(lines.parse_value(h)?);
+                        }
+                        _ => return Err(lines.error(format!("invalid size {:?}", value))),
+                    }
                 }
                 "[data]" => {
                     collecting_data = true;
                 }
                 "[additional]" => {
-                    let cnt = lines.next().unwrap().parse::<usize>().unwrap();
+                    let value = lines.value()?;
+                    let cnt: usize = lines.parse_value(value)?;
                     for _ in 0..cnt {
-                        let line = lines.next().unwrap();
+                        let line = lines.value()?;
                         let parts = line.split('.').collect::<Vec<&str>>();
-                        let x = parts[0].parse::<usize>().unwrap();
-                        let y = parts[1].parse::<usize>().unwrap();
-                        let c = parts[2].chars().next().unwrap();
-                        assert!(c == tiles[y].chars().nth(x).unwrap());
+                        if parts.len() < 3 {
+                            return Err(lines.error(format!("invalid entry {:?}", line)));
+                        }
+                        let x: usize = lines.parse_value(parts[0])?;
+                        let y: usize = lines.parse_value(parts[1])?;
+                        let c = parts[2].chars().next();
+                        let tile = tiles.get(y).and_then(|row| row.chars().nth(x));
+                        if c.is_none() || c != tile {
+                            return Err(lines.error(format!(
+                                "symbol {:?} at {}.{} doesn't match level data {:?}",
+                                parts[2], x, y, tile
+                            )));
+                        }
                         let params = parts[3..]
                             .iter()
-                            .map(|v| v.parse::<usize>().unwrap())
-                            .collect::<Vec<_>>();
+                            .map(|v| lines.parse_value(v))
+                            .collect::<Result<Vec<usize>, _>>()?;
+                        let (count, dirs, allowed) = additional_params(c.unwrap());
+                        if params.len() < count {
+                            return Err(lines.error(format!(
+                                "{:?} at {}.{} needs {} params",
+                                parts[2], x, y, count
+                            )));
+                        }
+                        if dirs.iter().any(|i| !allowed.contains(&params[*i])) {
+                            return Err(lines.error(format!(
+                                "invalid direction of {:?} at {}.{}",
+                                parts[2], x, y
+                            )));
+                        }
                         additional.insert((x as i32, y as i32), params);
                     }
                 }
                 "[end]" => {
-                    if color.is_none() {
-                        color = default_level_color.clone();
+                    let number = number.ok_or_else(|| lines.error("missing [level]"))?;
+                    let (width, height) = match (width, height) {
+                        (Some(width), Some(height)) => (width, height),
+                        _ => return Err(lines.error("missing [size]")),
+                    };
+                    if tiles.len() != height as usize {
+                        return Err(lines.error(format!(
+                            "level {} has {} rows of [data], expected {}",
+                            number,
+                            tiles.len(),
+                            height
+                        )));
                     }
-                    return Some(Level {
-                        number: number.unwrap(),
-                        width: width.unwrap(),
-                        height: height.unwrap(),
-                        color: color.unwrap(),
+                    if let Some(n) = tiles
+                        .iter()
+                        .position(|row| row.chars().count() != width as usize)
+                    {
+                        return Err(lines.error(format!(
+                            "level {}, row {} of [data] doesn't have {} symbols",
+                            number,
+                            n + 1,
+                            width
+                        )));
+                    }
+                    return Ok(Some(Level {
+                        number,
+                        width,
+                        height,
+                        color: color.unwrap_or_else(|| level_set.default_level_color.clone()),
                         tiles,
                         additional,
                         screw_count,
-                    });
+                        author,
+                        notes,
+                        offset,
+                    }));
                 }
                 _ => {
                     if collecting_data {
@@ -167,46 +374,140 @@ impl Level {
             }
         }
     }
-    // pub fn _get_color(&self) -> Color {
-    //     let rgb: Vec<f32> = (0..3)
-    //         .map(|i| i * 2)
-    //         .map(|i| &self.color[i..i + 2])
-    //         .map(|h| i32::from_str_radix(h, 16).unwrap() as f32 / 255.0)
-    //         .collect();
-    //     assert!(rgb.len() == 3);
-    //     Color::rgb(rgb[0], rgb[1], rgb[2])
-    // }
-}
-pub struct LevelSetIterator<'a> {
-    pub lines: std::str::Split<'a, char>,
 }
 
-impl<'a> Iterator for LevelSetIterator<'a> {
-    type Item = Level;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        Level::parse(&mut self.lines)
+/// colour in `rrggbb` hex notation (optionally prefixed with `#`)
+pub fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
     }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f32 / 255.0;
+    Some(Color::rgb(channel(0), channel(2), channel(4)))
 }
 
-#[derive(Debug, TypeUuid)]
+#[derive(Debug, PartialEq, Eq, TypeUuid)]
 #[uuid = "bc6b887f-3a1e-49f2-b101-8e14ab5ceae7"]
 pub struct LevelSet {
+    pub name: Option<String>,
+    /// [notes] of the whole levelset, one entry per line
+    pub notes: Vec<String>,
+    /// declared number of levels
+    pub last_level: Option<usize>,
+    pub default_level_color: String,
     pub levels: Vec<Level>,
+    /// levels that couldn't be parsed (and were skipped)
+    pub errors: Vec<LevelError>,
 }
 
 impl LevelSet {
+    /// Parses all valid levels, broken ones are skipped and reported in `errors`.
     pub fn new(data: &str) -> Self {
-        Self {
-            levels: LevelSetIterator {
-                lines: data.split('\n'),
+        let mut lines = LevelLines::new(data);
+        let mut level_set = Self {
+            name: None,
+            notes: vec![],
+            last_level: None,
+            default_level_color: String::from("608050"),
+            levels: vec![],
+            errors: vec![],
+        };
+        while let Some(result) = Level::parse(&mut lines, &mut level_set) {
+            match result {
+                Ok(level) => level_set.levels.push(level),
+                Err(err) => level_set.errors.push(err),
             }
-            .collect(),
+        }
+        // skipped levels are already reported
+        let count = level_set.levels.len() + level_set.errors.len();
+        match level_set.last_level {
+            Some(last_level) if last_level != count => level_set.errors.push(LevelError {
+                line: lines.last_level_line,
+                tag: String::from("[last_level]"),
+                message: format!("levelset has {} levels, not {}", count, last_level),
+            }),
+            _ => (),
+        }
+        level_set
+    }
+    /// Like `new`, but fails on the first broken level.
+    pub fn parse(data: &str) -> Result<Self, LevelError> {
+        let level_set = Self::new(data);
+        match level_set.errors.first() {
+            Some(err) => Err(err.clone()),
+            None => Ok(level_set),
         }
     }
     pub fn get(&self, n: usize) -> Option<&Level> {
         self.levels.get(n)
     }
+    /// background of the level: its [colour], or [default_level_colour] of the levelset
+    /// if that one is malformed
+    pub fn level_color(&self, level: &Level) -> Option<Color> {
+        parse_color(&level.color).or_else(|| {
+            warn!(
+                "invalid [colour] {:?} of level {}, using default",
+                level.color, level.number
+            );
+            parse_color(&self.default_level_color)
+        })
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(offset) = self.offset.as_ref() {
+            writeln!(f, "[offset]\n{}", offset)?;
+        }
+        writeln!(f, "[level]\n{}", self.number)?;
+        writeln!(f, "[colour]\n{}", self.color)?;
+        writeln!(f, "[size]\n{}.{}", self.width, self.height)?;
+        if let Some(author) = self.author.as_ref() {
+            writeln!(f, "[author]\n{}", author)?;
+        }
+        if !self.notes.is_empty() {
+            writeln!(f, "[level_notes]\n{}", self.notes.join("\n"))?;
+        }
+        writeln!(f, "[data]")?;
+        for row in &self.tiles {
+            writeln!(f, "{}", row)?;
+        }
+        if !self.additional.is_empty() {
+            let mut additional: Vec<_> = self.additional.iter().collect();
+            additional.sort_by_key(|((x, y), _)| (*y, *x));
+            writeln!(f, "[additional]\n{}", additional.len())?;
+            for ((x, y), params) in additional {
+                let c = self.tiles[*y as usize].chars().nth(*x as usize).unwrap();
+                write!(f, "{}.{}.{}", x, y, c)?;
+                for param in params {
+                    write!(f, ".{}", param)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        writeln!(f, "[end]")
+    }
+}
+
+/// GNU Robbo levelset format, `LevelSet::new` reads it back
+impl fmt::Display for LevelSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = self.name.as_ref() {
+            writeln!(f, "[name]\n{}", name)?;
+        }
+        if let Some(last_level) = self.last_level {
+            writeln!(f, "[last_level]\n{}", last_level)?;
+        }
+        writeln!(f, "[default_level_colour]\n{}", self.default_level_color)?;
+        if !self.notes.is_empty() {
+            writeln!(f, "[notes]\n{}", self.notes.join("\n"))?;
+        }
+        for level in &self.levels {
+            writeln!(f)?;
+            write!(f, "{}", level)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Default)]
@@ -221,6 +522,15 @@ impl AssetLoader for LevelSetLoader {
         Box::pin(async move {
             let data = std::str::from_utf8(bytes)?;
             let level_set = LevelSet::new(data);
+            for err in &level_set.errors {
+                warn!("{:?}: level skipped, {}", load_context.path(), err);
+            }
+            if level_set.levels.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "{:?}: no valid levels",
+                    load_context.path()
+                ));
+            }
             load_context.set_default_asset(LoadedAsset::new(level_set));
             Ok(())
         })
@@ -265,8 +575,8 @@ pub fn create_level(
                 '#' => create_static_box(commands),
                 '~' => create_push_box(commands),
                 '}' => create_gun(commands, additional.unwrap_or(&[0, 0, 0, 0, 0, 0])),
-                //'L' => create_horizontal_laser(commands),
-                //'l' => create_vertical_laser(commands),
+                'L' => create_horizontal_laser(commands, additional.unwrap_or(&[0])),
+                'l' => create_vertical_laser(commands, additional.unwrap_or(&[1])),
                 '&' => create_teleport(commands, additional.unwrap_or(&[0, 0])),
                 '^' => create_bird(commands, additional.unwrap_or(&[0, 0, 0, 0])),
                 '@' => {
@@ -278,8 +588,12 @@ pub fn create_level(
                 '\'' => create_ammo(commands),
                 'T' => create_screw(commands),
                 '%' => create_key(commands),
+                '+' => create_extra_life(commands),
                 '!' => create_capsule(commands),
                 'b' => create_bomb(commands),
+                'B' => create_bomb2(commands),
+                'X' => create_stop(commands),
+                'k' => create_radioactive_field(commands),
                 '?' => create_questionmark(commands),
                 '=' => create_forcefield(commands, additional.unwrap_or(&[0])[0]),
                 'M' => create_magnet(commands, additional.unwrap_or(&[0])[0]),
@@ -303,3 +617,140 @@ pub fn create_level(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str =
+        "[level]\n1\n[size]\n3.2\n[data]\nR.T\n@.!\n[additional]\n1\n0.1.@.2\n[end]\n";
+
+    #[test]
+    fn bundled_levelsets_are_valid() {
+        for data in &[
+            include_str!("../assets/original.txt"),
+            include_str!("../assets/forever.txt"),
+        ] {
+            assert!(!LevelSet::parse(data).unwrap().levels.is_empty());
+        }
+    }
+
+    #[test]
+    fn levelset_metadata() {
+        let original = LevelSet::parse(include_str!("../assets/original.txt")).unwrap();
+        assert_eq!(original.name.as_deref(), Some("Original"));
+        assert_eq!(original.last_level, Some(58));
+        assert_eq!(original.notes.len(), 5);
+        assert_eq!(original.levels[0].author.as_deref(), Some("Janusz Pelc"));
+        assert_eq!(
+            original.levels[0].notes,
+            vec!["level_notes relating to this level can go here."]
+        );
+        let forever = LevelSet::parse(include_str!("../assets/forever.txt")).unwrap();
+        assert_eq!(forever.name.as_deref(), Some("Forever"));
+        assert_eq!(forever.levels[0].offset.as_deref(), Some("509A"));
+        assert_eq!(forever.levels[0].author.as_deref(), Some("MWK"));
+    }
+
+    #[test]
+    fn last_level_is_checked() {
+        let data = format!("[last_level]\n2\n{}", LEVEL);
+        let err = LevelSet::parse(&data).unwrap_err();
+        assert_eq!((err.line, err.tag.as_str()), (2, "[last_level]"));
+        assert!(LevelSet::parse(&data.replace("\n2\n", "\n1\n")).is_ok());
+    }
+
+    #[test]
+    fn written_levelsets_parse_back() {
+        for data in &[
+            include_str!("../assets/original.txt"),
+            include_str!("../assets/forever.txt"),
+            LEVEL,
+        ] {
+            let level_set = LevelSet::parse(data).unwrap();
+            assert_eq!(LevelSet::parse(&level_set.to_string()), Ok(level_set));
+        }
+    }
+
+    #[test]
+    fn broken_levels_are_skipped() {
+        let data = format!(
+            "{}[level]\n2\n[size]\n3.x\n[data]\nR..\n[end]\n{}",
+            LEVEL,
+            LEVEL.replace("[level]\n1", "[level]\n3")
+        );
+        let level_set = LevelSet::new(&data);
+        let numbers: Vec<_> = level_set.levels.iter().map(|l| l.number).collect();
+        assert_eq!(numbers, vec![1, 3]);
+        assert_eq!(
+            level_set.errors,
+            vec![LevelError {
+                line: 15,
+                tag: "[size]".to_owned(),
+                message: "invalid number \"x\"".to_owned(),
+            }]
+        );
+        assert_eq!(LevelSet::parse(&data).unwrap_err(), level_set.errors[0]);
+    }
+
+    #[test]
+    fn level_without_end_does_not_hide_the_next_one() {
+        let data = format!(
+            "{}{}",
+            LEVEL.replace("[end]\n", ""),
+            LEVEL.replace("[level]\n1", "[level]\n2")
+        );
+        let level_set = LevelSet::new(&data);
+        let numbers: Vec<_> = level_set.levels.iter().map(|l| l.number).collect();
+        assert_eq!(numbers, vec![2]);
+        assert_eq!(
+            level_set.errors,
+            vec![LevelError {
+                line: 11,
+                tag: "[level]".to_owned(),
+                message: "previous level has no [end]".to_owned(),
+            }]
+        );
+    }
+
+    #[test]
+    fn level_errors_are_reported() {
+        let cases = &[
+            (LEVEL.replace("0.1.@.2", "1.1.@.2"), 10, "[additional]"),
+            (LEVEL.replace("0.1.@.2", "0.1.@.7"), 10, "[additional]"),
+            (LEVEL.replace("0.1.@.2", "0.1.@"), 10, "[additional]"),
+            (LEVEL.replace("R.T", "R."), 11, "[end]"),
+            (LEVEL.replace("@.!\n", ""), 9, "[additional]"),
+            (LEVEL.replace("@.!", "L.!").replace(".@.2", ".L.1"), 10, "[additional]"),
+            (LEVEL.replace("[end]\n", ""), 10, "[additional]"),
+            (LEVEL.replace("[size]\n3.2\n", ""), 9, "[end]"),
+        ];
+        for (data, line, tag) in cases {
+            let err = LevelSet::parse(data).unwrap_err();
+            assert_eq!((err.line, err.tag.as_str()), (*line, *tag), "{}", err);
+        }
+    }
+
+    #[test]
+    fn level_colour_falls_back_to_default() {
+        let data = format!("[default_level_colour]\n102030\n{}", LEVEL);
+        let level_set = LevelSet::parse(&data.replace("[size]", "[colour]\nff8000\n[size]"))
+            .unwrap();
+        assert_eq!(
+            level_set.level_color(&level_set.levels[0]),
+            Some(Color::rgb(1.0, 128.0 / 255.0, 0.0))
+        );
+        for color in &["", "ff80", "ff80zz", "ż8000"] {
+            let level_set =
+                LevelSet::parse(&data.replace("[size]", &format!("[colour]\n{}\n[size]", color)))
+                    .unwrap();
+            assert_eq!(
+                level_set.level_color(&level_set.levels[0]),
+                Some(Color::rgb(16.0 / 255.0, 32.0 / 255.0, 48.0 / 255.0)),
+                "{:?}",
+                color
+            );
+        }
+        assert_eq!(parse_color("#C0C0C0"), parse_color("c0c0c0"));
+    }
+}
@@ -1,3 +1,7 @@
+#[cfg(all(target_arch = "wasm32", feature = "ascii"))]
+mod ascii;
+#[cfg(any(test, feature = "tty", all(target_arch = "wasm32", feature = "ascii")))]
+mod board;
 mod components;
 mod entities;
 mod game_events;
@@ -5,17 +9,26 @@ mod inventory;
 mod levels;
 mod plugins;
 mod resources;
+mod snapshot;
+mod storage;
 mod systems;
+#[cfg(test)]
+mod tests;
+#[cfg(feature = "tty")]
+mod tty;
+mod verify;
 
 use bevy::prelude::*;
 use bevy::render::render_graph::RenderGraph;
 use bevy::render::renderer::{HeadlessRenderResourceContext, RenderResourceContext};
-use game_events::GameEvent;
-use inventory::Inventory;
-use levels::{LevelInfo, LevelSet, LevelSetLoader};
 use plugins::frame_cnt;
-use plugins::{AudioPlugin, FrameCnt, FrameCntPlugin, KeyboardPlugin};
-use resources::DamageMap;
+use plugins::progress::{Progress, ProgressStorage};
+use plugins::replay::Replay;
+use plugins::{
+    AudioPlugin, ControlsPlugin, FrameCnt, GameState, GameStatePlugin, KeyboardPlugin, ProgressPlugin,
+    QuickSavePlugin, ReplayPlugin, RewindPlugin, SimulationPlugin,
+};
+use storage::Storage;
 use structopt::StructOpt;
 use systems::*;
 
@@ -26,6 +39,7 @@ use systems::*;
 // static GLOBAL_ALLOCATOR: WasmTracingAllocator<System> = WasmTracingAllocator(System);
 
 mod consts {
+    /// cells of the board shown in the window, bigger levels scroll
     pub const MAX_BOARD_WIDTH: i32 = 31;
     pub const MAX_BOARD_HEIGHT: i32 = 16;
     pub const STATUS_HEIGHT: i32 = 2;
@@ -43,17 +57,64 @@ pub struct Opts {
     #[structopt(long)]
     pub no_audio: bool,
 
-    #[structopt(short, long, default_value = "1")]
-    pub level: usize,
+    /// level to start at (default: where the last game on this levelset stopped)
+    #[structopt(short, long)]
+    pub level: Option<usize>,
 
+    /// game speed: frames (1/60 s each) between keyframes
     #[structopt(short, long, default_value = "8")]
     pub key_frame_interval: usize,
 
+    /// rendered frames per second (doesn't change game speed)
     #[structopt(short, long, default_value = "60")]
     pub fps: usize,
 
     #[structopt(long, default_value = "original.txt")]
     pub levelset_path: std::path::PathBuf,
+
+    /// RNG seed; same seed and same input give the same game (random if not set)
+    #[structopt(long)]
+    pub seed: Option<u64>,
+
+    /// record input of current level attempt into a replay file
+    #[structopt(long)]
+    pub record: Option<std::path::PathBuf>,
+
+    /// play back a replay file instead of reading keyboard
+    #[structopt(long)]
+    pub replay: Option<std::path::PathBuf>,
+
+    /// directory where progress, quicksaves and controls are saved (native only, web uses localStorage)
+    #[structopt(long, default_value = ".")]
+    pub save_dir: std::path::PathBuf,
+
+    /// show on-screen D-pad and fire toggle (shown anyway on touch screens)
+    #[structopt(long)]
+    pub touch_controls: bool,
+
+    /// play in the terminal (drawn with characters, no window or GPU needed)
+    #[cfg(feature = "tty")]
+    #[structopt(long)]
+    pub tty: bool,
+
+    /// with --tty: milliseconds a key counts as held after the terminal last reported it,
+    /// longer than the terminal's auto repeat delay
+    #[cfg(feature = "tty")]
+    #[structopt(long, default_value = "500")]
+    pub tty_key_hold: u64,
+
+    /// classic game: limited lives, back to the first level when they run out
+    #[structopt(long)]
+    pub classic: bool,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub enum Command {
+    /// Run replays headlessly and check that Robbo reaches the capsule
+    Verify(verify::VerifyOpts),
 }
 
 pub fn render_graph_debug_system(
@@ -72,76 +133,125 @@ pub fn debug_system(shaders: Res<Assets<Shader>>, render_graph: Res<RenderGraph>
 }
 
 fn main() {
-    let opts = Opts::from_args();
+    let mut opts = Opts::from_args();
+    if let Some(Command::Verify(verify_opts)) = opts.command.as_ref() {
+        std::process::exit(verify::run(verify_opts));
+    }
+    let replay = match opts.replay.as_ref().map(|path| Replay::load(path)).transpose() {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("error: {:#}", err);
+            std::process::exit(1);
+        }
+    };
+    if let Some(replay) = replay.as_ref() {
+        opts.levelset_path = replay.levelset_path.clone();
+        opts.level = Some(replay.level);
+        opts.seed = Some(replay.seed);
+    }
+    let seed = *opts.seed.get_or_insert_with(rand::random);
+    // progress is kept for games played by hand only
+    let progress = if replay.is_none() && !opts.benchmark_mode {
+        let storage = Storage::new(&opts.save_dir, &opts.levelset_path, "progress");
+        let progress = Progress::load(&storage);
+        opts.level.get_or_insert(progress.current_level);
+        Some(ProgressPlugin {
+            storage: ProgressStorage(storage),
+            progress,
+        })
+    } else {
+        None
+    };
     info!("opts: {:?}", opts);
 
     let vsync = opts.fps == 60 && !opts.benchmark_mode;
     let mut builder = App::build();
 
-    builder.add_plugin(plugins::RenderPlugin { vsync });
-    builder
-        .add_resource(WindowDescriptor {
-            title: "Robbo".to_string(),
-            width: (32 * consts::MAX_BOARD_WIDTH) as f32,
-            height: (32 * (consts::MAX_BOARD_HEIGHT + consts::STATUS_HEIGHT)) as f32,
-            resizable: true,
-            // mode: window::WindowMode::Fullscreen {use_size: false},
-            mode: bevy::window::WindowMode::Windowed,
-            #[cfg(target_arch = "wasm32")]
-            canvas: Some("#bevy-canvas".to_string()),
-            vsync: vsync,
-            ..Default::default()
-        })
-        .add_plugins(DefaultPlugins);
+    #[cfg(feature = "tty")]
+    let tty = opts.tty;
+    #[cfg(not(feature = "tty"))]
+    let tty = false;
+
+    // web page without WebGL2, see `ascii/index.html`
+    let ascii = cfg!(all(target_arch = "wasm32", feature = "ascii"));
 
-    #[cfg(target_arch = "wasm32")]
-    builder.add_plugin(bevy_webgl2::WebGL2Plugin::default());
+    if tty {
+        #[cfg(feature = "tty")]
+        builder.add_plugin(tty::TtyPlugin {
+            fps: opts.fps,
+            key_hold_time: std::time::Duration::from_millis(opts.tty_key_hold),
+        });
+    } else if ascii {
+        #[cfg(all(target_arch = "wasm32", feature = "ascii"))]
+        builder.add_plugin(ascii::AsciiPlugin);
+    } else {
+        builder.add_plugin(plugins::RenderPlugin {
+            vsync,
+            touch_controls: opts.touch_controls,
+        });
+        builder
+            .add_resource(WindowDescriptor {
+                title: "Robbo".to_string(),
+                width: (32 * consts::MAX_BOARD_WIDTH) as f32,
+                height: (32 * (consts::MAX_BOARD_HEIGHT + consts::STATUS_HEIGHT)) as f32,
+                resizable: true,
+                // mode: window::WindowMode::Fullscreen {use_size: false},
+                mode: bevy::window::WindowMode::Windowed,
+                #[cfg(target_arch = "wasm32")]
+                canvas: Some("#bevy-canvas".to_string()),
+                vsync: vsync,
+                ..Default::default()
+            })
+            .add_plugins(DefaultPlugins);
+
+        #[cfg(all(target_arch = "wasm32", feature = "web"))]
+        builder.add_plugin(bevy_webgl2::WebGL2Plugin::default());
+    }
 
+    let simulation = SimulationPlugin::new(opts.key_frame_interval, seed);
+    // benchmark runs as fast as possible
+    let simulation = if opts.benchmark_mode {
+        simulation
+    } else {
+        simulation.with_real_time()
+    };
     builder
-        .add_resource(Inventory::default())
-        .add_resource(LevelInfo::default())
-        .add_resource(DamageMap::default())
-        .add_resource(Events::<GameEvent>::default())
         .add_resource(opts.clone())
-        .add_asset::<LevelSet>()
-        .init_asset_loader::<LevelSetLoader>()
-        .add_plugin(FrameCntPlugin::new(opts.key_frame_interval))
-        .add_plugin(KeyboardPlugin)
+        .add_plugin(simulation)
         .add_plugin(AudioPlugin)
-        .add_stage_before(stage::UPDATE, "move", SystemStage::parallel())
-        .add_stage_before(stage::UPDATE, "move_robbo", SystemStage::parallel())
-        .add_stage_before(stage::POST_UPDATE, "reload_level", SystemStage::parallel())
-        .add_stage_before(stage::POST_UPDATE, "shots", SystemStage::parallel())
-        .add_stage_before(
-            stage::POST_UPDATE,
-            "process_damage",
-            SystemStage::parallel(),
-        )
-        .add_stage_before(stage::POST_UPDATE, "game_events", SystemStage::parallel())
-        .add_stage_after("keyboard", "magnetic_field", SystemStage::parallel())
-        .add_stage_after("frame_cnt", "tick", SystemStage::parallel())
+        .add_plugin(ControlsPlugin {
+            storage: Storage::named(&opts.save_dir, "controls"),
+        })
         .add_startup_system(level_setup.system())
-        .add_system_to_stage(stage::EVENT, update_game_events.system())
-        .add_system_to_stage(stage::EVENT, asset_events.system())
-        .add_system_to_stage("magnetic_field", magnetic_field_system.system())
-        .add_system_to_stage("process_damage", process_damage.system())
-        .add_system_to_stage("move", move_laser_head.system())
-        .add_system_to_stage("move", move_bear.system())
-        .add_system_to_stage("move", move_bird.system())
-        .add_system_to_stage("move", move_pushbox.system())
-        .add_system_to_stage("move", move_bullet.system())
-        .add_system_to_stage("move", move_blaster_head.system())
-        .add_system_to_stage("move", eyes_system.system())
-        .add_system_to_stage("move", force_field_system.system())
-        .add_system_to_stage("move_robbo", move_robbo.system())
-        .add_system_to_stage("shots", shot_system.system())
-        .add_system_to_stage("game_events", game_event_system.system())
-        .add_system_to_stage("game_events", reload_level_system.system())
-        .add_system_to_stage("game_events", game_event_use_item.system())
-        .add_system_to_stage("game_events", game_event_use_teleport.system())
-        .add_system_to_stage("tick", activate_capsule_system.system())
-        .add_system_to_stage("tick", tick_system.system())
-        .add_system_to_stage("tick", damage_system.system());
+        .add_system_to_stage(stage::EVENT, asset_events.system());
+
+    if opts.classic {
+        builder.add_resource(inventory::Inventory::classic());
+    }
+
+    if !opts.benchmark_mode {
+        builder.add_plugin(GameStatePlugin);
+    }
+
+    if let Some(replay) = replay {
+        builder.add_plugin(ReplayPlugin::Play(replay));
+    } else {
+        if !opts.benchmark_mode {
+            builder.add_resource(State::new(GameState::Title));
+        }
+        if let Some(progress) = progress {
+            builder.add_plugin(progress).add_plugin(QuickSavePlugin {
+                storage: Storage::new(&opts.save_dir, &opts.levelset_path, "quicksave"),
+            });
+        }
+        builder
+            .add_plugin(KeyboardPlugin)
+            .add_plugin(RewindPlugin)
+            .add_system_to_stage("keyboard", plugins::rewind::rewind_keyboard.system());
+        if let Some(path) = opts.record.as_ref() {
+            builder.add_plugin(ReplayPlugin::Record(path.clone()));
+        }
+    }
 
     if opts.debug {
         builder.add_plugin(bevy::diagnostic::FrameTimeDiagnosticsPlugin::default());
@@ -150,7 +260,8 @@ fn main() {
 
     if !opts.benchmark_mode {
         builder.add_system_to_stage("reload_level", reload_level.system());
-        if !vsync {
+        // the terminal runner keeps fps itself
+        if !vsync && !tty {
             #[cfg(not(target_arch = "wasm32"))]
             builder.add_plugin(plugins::FrameLimiterPlugin {
                 fps: opts.fps as f32,
@@ -159,5 +270,17 @@ fn main() {
     } else {
         builder.add_system_to_stage("reload_level", benchmark_reload_level.system());
     }
+    #[cfg(feature = "tty")]
+    let _terminal = if tty {
+        match tty::Terminal::new() {
+            Ok(terminal) => Some(terminal),
+            Err(err) => {
+                eprintln!("error: cannot set up terminal: {}", err);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
     builder.run();
 }
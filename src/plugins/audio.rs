@@ -17,9 +17,8 @@ pub enum Sound {
 pub struct AudioPlugin;
 
 impl Plugin for AudioPlugin {
+    #[allow(unused_variables)]
     fn build(&self, app: &mut AppBuilder) {
-        app.add_resource(Events::<Sound>::default());
-        app.add_system_to_stage(stage::EVENT, Events::<Sound>::update_system.system());
         #[cfg(feature="audio")]
         {
             app.add_startup_system(audio_setup.system());
@@ -0,0 +1,340 @@
+use crate::plugins::GameState;
+use crate::storage::Storage;
+use anyhow::anyhow;
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// held with a direction: shoot instead of moving
+    Fire,
+    /// shoot where Robbo faces
+    FireFacing,
+    Kill,
+    NextLevel,
+    PrevLevel,
+    Pause,
+    Confirm,
+    Rewind,
+    QuickSave,
+    QuickLoad,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Fire,
+        Action::FireFacing,
+        Action::Kill,
+        Action::NextLevel,
+        Action::PrevLevel,
+        Action::Pause,
+        Action::Confirm,
+        Action::Rewind,
+        Action::QuickSave,
+        Action::QuickLoad,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Fire => "fire",
+            Action::FireFacing => "fire_facing",
+            Action::Kill => "kill",
+            Action::NextLevel => "next_level",
+            Action::PrevLevel => "prev_level",
+            Action::Pause => "pause",
+            Action::Confirm => "confirm",
+            Action::Rewind => "rewind",
+            Action::QuickSave => "quicksave",
+            Action::QuickLoad => "quickload",
+        }
+    }
+}
+
+/// keys that may be bound, named as `KeyCode` variants
+static BINDABLE_KEYS: &[KeyCode] = {
+    use KeyCode::*;
+    &[
+        Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0, A, B, C, D, E, F, G, H, I, J,
+        K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Escape, F1, F3, F4, F5, F6, F7, F8, F9,
+        F10, F11, F12, Pause, Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down,
+        Back, Return, Space, Tab, Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6,
+        Numpad7, Numpad8, Numpad9, NumpadAdd, NumpadSubtract, NumpadMultiply, NumpadDivide,
+        NumpadDecimal, NumpadEnter, Apostrophe, Backslash, Comma, Equals, Grave, LBracket,
+        RBracket, Minus, Period, Semicolon, Slash, LAlt, RAlt, LControl, RControl, LShift,
+        RShift,
+    ]
+};
+
+/// opens the controls editor, not bindable so it can't be lost
+pub const EDIT_CONTROLS_KEY: KeyCode = KeyCode::F2;
+
+fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|key| key_name(*key) == name)
+}
+
+/// Keys bound to every action
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bindings {
+    keys: BTreeMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use KeyCode::*;
+        let keys = vec![
+            (Action::Up, vec![Up, W]),
+            (Action::Down, vec![Down, S]),
+            (Action::Left, vec![Left, A]),
+            (Action::Right, vec![Right, D]),
+            (Action::Fire, vec![LShift, RShift]),
+            (Action::FireFacing, vec![LControl, RControl]),
+            (Action::Kill, vec![Escape]),
+            (Action::NextLevel, vec![PageUp]),
+            (Action::PrevLevel, vec![PageDown]),
+            (Action::Pause, vec![P, Pause]),
+            (Action::Confirm, vec![Return, Space]),
+            (Action::Rewind, vec![Back]),
+            (Action::QuickSave, vec![F5]),
+            (Action::QuickLoad, vec![F9]),
+        ];
+        Bindings {
+            keys: keys.into_iter().collect(),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+    pub fn bind(&mut self, action: Action, keys: Vec<KeyCode>) {
+        self.keys.insert(action, keys);
+    }
+    /// action the key is bound to
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.keys
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+    pub fn pressed(&self, input: &Input<KeyCode>, action: Action) -> bool {
+        self.keys(action).iter().any(|key| input.pressed(*key))
+    }
+    pub fn just_pressed(&self, input: &Input<KeyCode>, action: Action) -> bool {
+        self.keys(action).iter().any(|key| input.just_pressed(*key))
+    }
+
+    /// `[action]` tag followed by a line of space separated keys,
+    /// actions missing in the file keep their default keys
+    pub fn parse(data: &str) -> anyhow::Result<Bindings> {
+        let mut bindings = Bindings::default();
+        let mut lines = data.lines().enumerate();
+        while let Some((n, line)) = lines.next() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let action = Action::ALL
+                .iter()
+                .copied()
+                .find(|action| format!("[{}]", action.name()) == line)
+                .ok_or_else(|| anyhow!("line {}: unknown action {:?}", n + 1, line))?;
+            let keys = lines.next().map(|(_, keys)| keys).unwrap_or("");
+            let keys = keys
+                .split_whitespace()
+                .map(|name| {
+                    parse_key(name).ok_or_else(|| anyhow!("line {}: unknown key {:?}", n + 2, name))
+                })
+                .collect::<anyhow::Result<_>>()?;
+            bindings.bind(action, keys);
+        }
+        Ok(bindings)
+    }
+
+    pub fn load(storage: &Storage) -> Bindings {
+        match storage.read() {
+            Ok(Some(data)) => Bindings::parse(&data).unwrap_or_else(|err| {
+                warn!("invalid controls in {}: {}", storage, err);
+                Bindings::default()
+            }),
+            Ok(None) => Bindings::default(),
+            Err(err) => {
+                warn!("cannot read controls from {}: {}", storage, err);
+                Bindings::default()
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &Storage) {
+        match storage.write(&self.to_string()) {
+            Ok(()) => info!("controls saved into {}", storage),
+            Err(err) => warn!("cannot save controls into {}: {}", storage, err),
+        }
+    }
+}
+
+impl fmt::Display for Bindings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for action in Action::ALL {
+            let keys: Vec<_> = self.keys(*action).iter().map(|key| key_name(*key)).collect();
+            writeln!(f, "[{}]\n{}", action.name(), keys.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// In-game editing of bindings: every action in turn gets the keys pressed
+/// until `EDIT_CONTROLS_KEY`, keys of other actions are refused
+#[derive(Default)]
+pub struct ControlsEditor {
+    /// action being bound
+    action: Option<usize>,
+    /// new keys of the action being bound
+    keys: Vec<KeyCode>,
+    return_state: Option<GameState>,
+    /// shown in the window title while editing
+    pub prompt: Option<String>,
+}
+
+impl ControlsEditor {
+    pub fn start(&mut self, bindings: &Bindings) {
+        self.action = Some(0);
+        self.keys.clear();
+        self.prompt = Some(editor_prompt(bindings, Action::ALL[0], &[]));
+    }
+
+    /// Handles a key pressed while editing, returns true when all actions are bound
+    pub fn press(&mut self, bindings: &mut Bindings, key: KeyCode) -> bool {
+        let index = match self.action {
+            Some(index) => index,
+            None => return false,
+        };
+        let action = Action::ALL[index];
+        if key != EDIT_CONTROLS_KEY {
+            match bindings.action(key).filter(|other| *other != action) {
+                Some(other) => {
+                    self.prompt = Some(format!(
+                        "{} - {:?} is used by {}",
+                        editor_prompt(bindings, action, &self.keys),
+                        key,
+                        other.name()
+                    ));
+                }
+                None => {
+                    if !self.keys.contains(&key) {
+                        self.keys.push(key);
+                    }
+                    self.prompt = Some(editor_prompt(bindings, action, &self.keys));
+                }
+            }
+            return false;
+        }
+        // no new keys: the current ones are kept
+        if !self.keys.is_empty() {
+            bindings.bind(action, std::mem::take(&mut self.keys));
+        }
+        match Action::ALL.get(index + 1) {
+            Some(&next) => {
+                self.action = Some(index + 1);
+                self.prompt = Some(editor_prompt(bindings, next, &[]));
+                false
+            }
+            None => {
+                self.action = None;
+                self.prompt = None;
+                true
+            }
+        }
+    }
+}
+
+pub struct ControlsPlugin {
+    pub storage: Storage,
+}
+
+pub struct ControlsStorage(pub Storage);
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(Bindings::load(&self.storage))
+            .add_resource(ControlsStorage(self.storage.clone()))
+            .add_resource(ControlsEditor::default())
+            .add_stage_after(stage::EVENT, "controls", SystemStage::parallel())
+            .add_system_to_stage("controls", controls_editor.system());
+    }
+}
+
+fn editor_prompt(bindings: &Bindings, action: Action, new_keys: &[KeyCode]) -> String {
+    let names = |keys: &[KeyCode]| {
+        keys.iter()
+            .map(|key| key_name(*key))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    if new_keys.is_empty() {
+        format!(
+            "press keys for {} (now {}, {:?} keeps them)",
+            action.name(),
+            names(bindings.keys(action)),
+            EDIT_CONTROLS_KEY
+        )
+    } else {
+        format!(
+            "press keys for {} (new {}, {:?} when done)",
+            action.name(),
+            names(new_keys),
+            EDIT_CONTROLS_KEY
+        )
+    }
+}
+
+pub fn controls_editor(
+    keyboard_input: Res<Input<KeyCode>>,
+    (storage, mut bindings, mut editor): (
+        Res<ControlsStorage>,
+        ResMut<Bindings>,
+        ResMut<ControlsEditor>,
+    ),
+    mut state: ResMut<State<GameState>>,
+) {
+    if editor.action.is_none() {
+        if keyboard_input.just_pressed(EDIT_CONTROLS_KEY) {
+            editor.start(&bindings);
+            editor.return_state = Some(match *state.current() {
+                GameState::Playing => GameState::Paused,
+                current => current,
+            });
+            state.set_next(GameState::Controls).ok();
+        }
+        return;
+    }
+    let key = match keyboard_input.get_just_pressed().next() {
+        Some(&key) if key == EDIT_CONTROLS_KEY || parse_key(&key_name(key)).is_some() => key,
+        _ => return,
+    };
+    if editor.press(&mut bindings, key) {
+        bindings.save(&storage.0);
+        let return_state = editor.return_state.take().unwrap_or(GameState::Paused);
+        state.set_next(return_state).ok();
+    }
+}
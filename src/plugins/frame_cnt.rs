@@ -1,12 +1,28 @@
+use crate::plugins::simulation::gameplay_stage;
 use bevy::prelude::*;
 
+/// frames of real time play per second (game speed doesn't depend on rendered fps)
+pub const FRAMES_PER_SECOND: f64 = 60.0;
+
 pub struct FrameCntPlugin {
     key_frame_interval: usize,
+    real_time: bool,
 }
 
 impl FrameCntPlugin {
     pub fn new(key_frame_interval: usize) -> FrameCntPlugin {
-        FrameCntPlugin { key_frame_interval }
+        FrameCntPlugin {
+            key_frame_interval,
+            real_time: false,
+        }
+    }
+
+    /// frames follow the clock (`FRAMES_PER_SECOND`) instead of one per update
+    pub fn with_real_time(self) -> FrameCntPlugin {
+        FrameCntPlugin {
+            real_time: true,
+            ..self
+        }
     }
 }
 
@@ -19,20 +35,55 @@ impl Default for FrameCntPlugin {
 pub struct FrameCnt {
     cnt: usize,
     key_frame_interval: usize,
+    /// counter has just reached a keyframe
+    is_keyframe: bool,
+    /// real time not turned into frames yet (in seconds), `None` if not in real time
+    time_left: Option<f64>,
 }
 
 impl FrameCnt {
     pub fn is_keyframe(&self) -> bool {
-        (self.cnt % self.key_frame_interval) == 0
+        self.is_keyframe
     }
 
     pub fn value(&self) -> usize {
         self.cnt
     }
     pub fn inc(&mut self) -> usize {
-        self.cnt += 1;
+        self.set(self.cnt + 1);
         self.cnt
     }
+    /// advances by frames due after `seconds` of real time, but never past the next keyframe,
+    /// so every keyframe happens (slow rendering slows the game down rather than skipping)
+    pub fn advance(&mut self, seconds: f64) {
+        let frame_time = 1.0 / FRAMES_PER_SECOND;
+        // don't rush after stalls
+        let max_time_left = self.key_frame_interval as f64 * frame_time;
+        let time_left = (self.time_left.unwrap_or(0.0) + seconds).min(max_time_left);
+        let to_keyframe = self.key_frame_interval - self.cnt % self.key_frame_interval;
+        let frames = ((time_left / frame_time) as usize).min(to_keyframe);
+        self.time_left = Some(time_left - frames as f64 * frame_time);
+        if frames > 0 {
+            self.set(self.cnt + frames);
+        } else {
+            self.is_keyframe = false;
+        }
+    }
+    /// part of the time between keyframes already passed, in [0, 1)
+    pub fn keyframe_progress(&self) -> f32 {
+        let frames = (self.cnt % self.key_frame_interval) as f64
+            + self.time_left.unwrap_or(0.0) * FRAMES_PER_SECOND;
+        (frames / self.key_frame_interval as f64).min(0.999) as f32
+    }
+    /// keyframes since `start_frame`
+    pub fn ticks_since(&self, start_frame: usize) -> usize {
+        self.cnt.saturating_sub(start_frame) / self.key_frame_interval
+    }
+    /// used when the board is rewound to an earlier keyframe
+    pub fn set(&mut self, cnt: usize) {
+        self.cnt = cnt;
+        self.is_keyframe = (cnt % self.key_frame_interval) == 0;
+    }
 }
 
 impl Plugin for FrameCntPlugin {
@@ -40,11 +91,18 @@ impl Plugin for FrameCntPlugin {
         app.add_resource(FrameCnt {
             key_frame_interval: self.key_frame_interval,
             cnt: 0,
+            is_keyframe: true,
+            time_left: if self.real_time { Some(0.0) } else { None },
         })
-        .add_stage_before(stage::LAST, "frame_cnt", SystemStage::parallel())
+        // frame counter is stopped when game is paused
+        .add_stage_before(stage::LAST, "frame_cnt", gameplay_stage())
         .add_system_to_stage("frame_cnt", frame_cnt_system.system());
     }
 }
-fn frame_cnt_system(mut frame_cnt: ResMut<FrameCnt>) {
-    frame_cnt.inc();
+fn frame_cnt_system(time: Res<Time>, mut frame_cnt: ResMut<FrameCnt>) {
+    if frame_cnt.time_left.is_some() {
+        frame_cnt.advance(time.delta_seconds_f64());
+    } else {
+        frame_cnt.inc();
+    }
 }
@@ -0,0 +1,72 @@
+use crate::plugins::controls::{Action, Bindings};
+use crate::plugins::gamepad::start_just_pressed;
+use crate::plugins::touch::TouchControls;
+use bevy::ecs::ShouldRun;
+use bevy::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameState {
+    Title,
+    Playing,
+    Paused,
+    /// Robbo flew away with the capsule, next level is waiting
+    LevelComplete,
+    /// no lives left (classic mode)
+    GameOver,
+    /// keys are being rebound, see `ControlsEditor`
+    Controls,
+}
+
+impl GameState {
+    /// what the player is asked to do, `None` while playing
+    pub fn prompt(&self) -> Option<&'static str> {
+        match self {
+            GameState::Title => Some("press Enter to start"),
+            GameState::Playing => None,
+            GameState::Paused => Some("paused, press P to continue"),
+            GameState::LevelComplete => Some("level complete, press Enter to continue"),
+            GameState::GameOver => Some("game over, press Enter"),
+            GameState::Controls => Some("editing controls"),
+        }
+    }
+}
+
+/// Run criteria of gameplay stages: the board (and frame counter) is frozen unless playing
+pub fn run_if_playing(state: Res<State<GameState>>) -> ShouldRun {
+    if *state.current() == GameState::Playing {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+/// Keyboard (gamepad Start button, touch controls) driven transitions between game states
+/// (the state itself is owned by `SimulationPlugin`)
+pub struct GameStatePlugin;
+
+impl Plugin for GameStatePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_stage_after(stage::EVENT, "game_state_input", SystemStage::parallel())
+            .add_system_to_stage("game_state_input", game_state_keyboard.system());
+    }
+}
+
+pub fn game_state_keyboard(
+    (keyboard_input, bindings): (Res<Input<KeyCode>>, Res<Bindings>),
+    (gamepad_buttons, touch): (Res<Input<GamepadButton>>, Res<TouchControls>),
+    mut state: ResMut<State<GameState>>,
+) {
+    let start = start_just_pressed(&gamepad_buttons);
+    let confirm =
+        bindings.just_pressed(&keyboard_input, Action::Confirm) || start || touch.confirm;
+    let pause = bindings.just_pressed(&keyboard_input, Action::Pause) || start || touch.pause;
+    let next = match *state.current() {
+        GameState::Title | GameState::LevelComplete if confirm => GameState::Playing,
+        GameState::Playing if pause => GameState::Paused,
+        GameState::Paused if pause || confirm => GameState::Playing,
+        GameState::GameOver if confirm => GameState::Title,
+        _ => return,
+    };
+    info!("game state: {:?}", next);
+    state.set_next(next).ok();
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+
+/// stick deflection treated as a pressed direction
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// face buttons which turn a direction into a shot (like shift on keyboard)
+const FIRE_BUTTONS: &[GamepadButtonType] = &[GamepadButtonType::South, GamepadButtonType::West];
+
+/// face button shooting where Robbo faces
+const FIRE_FACING_BUTTON: GamepadButtonType = GamepadButtonType::East;
+
+/// Pressed directions, as (right, left, up, down)
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Dirs {
+    pub right: bool,
+    pub left: bool,
+    pub up: bool,
+    pub down: bool,
+}
+
+impl Dirs {
+    pub fn or(self, other: Dirs) -> Dirs {
+        Dirs {
+            right: self.right || other.right,
+            left: self.left || other.left,
+            up: self.up || other.up,
+            down: self.down || other.down,
+        }
+    }
+
+    /// directions pressed now but not in `previous`
+    pub fn since(self, previous: Dirs) -> Dirs {
+        Dirs {
+            right: self.right && !previous.right,
+            left: self.left && !previous.left,
+            up: self.up && !previous.up,
+            down: self.down && !previous.down,
+        }
+    }
+}
+
+/// State of all connected gamepads merged together, read by `keyboard_system`
+#[derive(Default, Debug)]
+pub struct GamepadInput {
+    pub pressed: Dirs,
+    pub just_pressed: Dirs,
+    pub fire: bool,
+    pub fire_facing: bool,
+    gamepads: Vec<Gamepad>,
+}
+
+/// Start button pressed on any gamepad (confirms and pauses)
+pub fn start_just_pressed(buttons: &Input<GamepadButton>) -> bool {
+    buttons
+        .get_just_pressed()
+        .any(|button| button.1 == GamepadButtonType::Start)
+}
+
+fn stick_dirs(
+    axes: &Axis<GamepadAxis>,
+    gamepad: Gamepad,
+    x: GamepadAxisType,
+    y: GamepadAxisType,
+) -> Dirs {
+    let x = axes.get(GamepadAxis(gamepad, x)).unwrap_or(0.0);
+    let y = axes.get(GamepadAxis(gamepad, y)).unwrap_or(0.0);
+    Dirs {
+        right: x > STICK_THRESHOLD,
+        left: x < -STICK_THRESHOLD,
+        up: y > STICK_THRESHOLD,
+        down: y < -STICK_THRESHOLD,
+    }
+}
+
+/// Runs every frame (also when the board is frozen), so connections and releases are not missed
+pub fn gamepad_system(
+    mut events: Local<EventReader<GamepadEvent>>,
+    gamepad_events: Res<Events<GamepadEvent>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut input: ResMut<GamepadInput>,
+) {
+    for GamepadEvent(gamepad, event_type) in events.iter(&gamepad_events) {
+        match event_type {
+            GamepadEventType::Connected => {
+                info!("gamepad {} connected", gamepad.0);
+                input.gamepads.push(*gamepad);
+            }
+            GamepadEventType::Disconnected => {
+                info!("gamepad {} disconnected", gamepad.0);
+                input.gamepads.retain(|connected| connected != gamepad);
+            }
+            _ => (),
+        }
+    }
+    let is_pressed = |button_type| buttons.get_pressed().any(|button| button.1 == button_type);
+    let mut pressed = Dirs {
+        right: is_pressed(GamepadButtonType::DPadRight),
+        left: is_pressed(GamepadButtonType::DPadLeft),
+        up: is_pressed(GamepadButtonType::DPadUp),
+        down: is_pressed(GamepadButtonType::DPadDown),
+    };
+    for gamepad in &input.gamepads {
+        pressed = pressed
+            .or(stick_dirs(
+                &axes,
+                *gamepad,
+                GamepadAxisType::LeftStickX,
+                GamepadAxisType::LeftStickY,
+            ))
+            .or(stick_dirs(
+                &axes,
+                *gamepad,
+                GamepadAxisType::DPadX,
+                GamepadAxisType::DPadY,
+            ));
+    }
+    input.just_pressed = pressed.since(input.pressed);
+    input.pressed = pressed;
+    input.fire = FIRE_BUTTONS
+        .iter()
+        .any(|button_type| is_pressed(*button_type));
+    input.fire_facing = buttons
+        .get_just_pressed()
+        .any(|button| button.1 == FIRE_FACING_BUTTON);
+}
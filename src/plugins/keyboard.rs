@@ -4,100 +4,196 @@ use crate::components::prelude::*;
 use crate::game_events::GameEvent;
 use crate::inventory::Inventory;
 use crate::plugins::audio::Sound;
+use crate::plugins::controls::{Action, Bindings};
+use crate::plugins::gamepad::{gamepad_system, Dirs, GamepadInput};
+use crate::plugins::replay::ReplayFrame;
+use crate::plugins::simulation::gameplay_stage;
+use crate::plugins::touch::{touch_system, TouchControls};
 use crate::FrameCnt;
+use std::collections::VecDeque;
+
+/// taps waiting for keyframes, further ones are dropped (mashing keys doesn't queue up moves)
+const MAX_QUEUED_INPUTS: usize = 4;
 
 pub struct KeyboardPlugin;
 
 impl Plugin for KeyboardPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_resource(KeyboardPlugin)
-            .add_resource(RobboDir::default())
-            .add_stage_before(stage::PRE_UPDATE, "keyboard", SystemStage::parallel())
-            .add_stage_after(stage::POST_UPDATE, "robbo_dir_reset", SystemStage::parallel())
-            .add_system_to_stage("keyboard", keyboard_system.system())
-            .add_system_to_stage("robbo_dir_reset", robbo_dir_reset.system());
+            .add_resource(InputQueue::default())
+            .add_resource(GamepadInput::default())
+            .add_stage_after(stage::EVENT, "keyboard", gameplay_stage())
+            .add_stage_before("keyboard", "input_devices", SystemStage::parallel())
+            .add_system_to_stage("input_devices", gamepad_system.system())
+            .add_system_to_stage("input_devices", touch_system.system())
+            .add_system_to_stage("keyboard", keyboard_system.system());
+    }
+}
+
+/// Player's input between keyframes. Every tap (press of a direction, shot, kill,
+/// level change) is queued and applied on its own keyframe, in order;
+/// keyframes without queued taps move Robbo in the held direction.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct InputQueue {
+    pub queue: VecDeque<ReplayFrame>,
+    /// input applied at the last keyframe
+    pub applied: ReplayFrame,
+}
+
+impl InputQueue {
+    fn push(&mut self, frame: ReplayFrame) {
+        if self.queue.len() < MAX_QUEUED_INPUTS {
+            self.queue.push_back(frame);
+        }
     }
 }
 
 pub fn keyboard_system(
     commands: &mut Commands,
-    keyboard_input: Res<Input<KeyCode>>,
-    (mut events, mut inventory, mut sounds): (ResMut<Events<GameEvent>>, ResMut<Inventory>, ResMut<Events<Sound>>),
-    mut robbo_dir: ResMut<RobboDir>,
-    mut query: Query<(Entity, &mut MovingDir, &mut Tiles), With<Robbo>>,
+    (keyboard_input, bindings, gamepad, touch): (
+        Res<Input<KeyCode>>,
+        Res<Bindings>,
+        Res<GamepadInput>,
+        Res<TouchControls>,
+    ),
+    frame_cnt: Res<FrameCnt>,
+    (mut events, mut inventory, mut sounds): (
+        ResMut<Events<GameEvent>>,
+        ResMut<Inventory>,
+        ResMut<Events<Sound>>,
+    ),
+    mut input: ResMut<InputQueue>,
+    mut query: Query<(Entity, &mut MovingDir, &mut Tiles, Option<&ShootingDir>), With<Robbo>>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
-        events.send(GameEvent::KillRobbo);
-        return;
-    } else if keyboard_input.just_pressed(KeyCode::PageUp) {
-        events.send(GameEvent::ReloadLevel(1));
-        return;
-    } else if keyboard_input.just_pressed(KeyCode::PageDown) {
-        events.send(GameEvent::ReloadLevel(-1));
-        return;
+    let level_change = if bindings.just_pressed(&keyboard_input, Action::NextLevel) {
+        1
+    } else if bindings.just_pressed(&keyboard_input, Action::PrevLevel) {
+        -1
+    } else {
+        0
+    };
+    let kill = bindings.just_pressed(&keyboard_input, Action::Kill);
+    if kill || level_change != 0 {
+        // taps made before the level restarts don't matter
+        input.queue.clear();
+        input.push(ReplayFrame {
+            kill,
+            level_change,
+            ..Default::default()
+        });
     }
-    let is_shift =
-        keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::RShift);
-
-    let jp_right = (keyboard_input.just_pressed(KeyCode::Right)
-        || keyboard_input.just_pressed(KeyCode::D)) as i32;
-    let jp_left = (keyboard_input.just_pressed(KeyCode::Left)
-        || keyboard_input.just_pressed(KeyCode::A)) as i32;
-    let jp_up = (keyboard_input.just_pressed(KeyCode::Up)
-        || keyboard_input.just_pressed(KeyCode::W)) as i32;
-    let jp_down = (keyboard_input.just_pressed(KeyCode::Down)
-        || keyboard_input.just_pressed(KeyCode::S)) as i32;
+    let is_shift = bindings.pressed(&keyboard_input, Action::Fire) || gamepad.fire || touch.fire;
 
-    let right =
-        (keyboard_input.pressed(KeyCode::Right) || keyboard_input.pressed(KeyCode::D)) as i32;
-    let left = (keyboard_input.pressed(KeyCode::Left) || keyboard_input.pressed(KeyCode::A)) as i32;
-    let up = (keyboard_input.pressed(KeyCode::Up) || keyboard_input.pressed(KeyCode::W)) as i32;
-    let down = (keyboard_input.pressed(KeyCode::Down) || keyboard_input.pressed(KeyCode::S)) as i32;
+    let dirs = |is_pressed: &dyn Fn(Action) -> bool| Dirs {
+        right: is_pressed(Action::Right),
+        left: is_pressed(Action::Left),
+        up: is_pressed(Action::Up),
+        down: is_pressed(Action::Down),
+    };
+    let just_pressed = dirs(&|action| bindings.just_pressed(&keyboard_input, action))
+        .or(gamepad.just_pressed)
+        .or(touch.just_pressed);
+    let pressed = dirs(&|action| bindings.pressed(&keyboard_input, action))
+        .or(gamepad.pressed)
+        .or(touch.pressed);
 
-    for (entity, mut moving_dir, mut tiles) in query.iter_mut() {
-        if is_shift {
-            let kx = jp_right - jp_left;
-            let ky = jp_up - jp_down;
-            if (kx != 0 || ky != 0) && inventory.bullets > 0 {
-                inventory.bullets -= 1;
-                *moving_dir = MovingDir::zero();
-                commands.insert_one(entity, ShootingDir::new(kx, ky));
-                sounds.send(Sound::SHOT);
+    let taps = [
+        (just_pressed.right, 1, 0),
+        (just_pressed.left, -1, 0),
+        (just_pressed.up, 0, 1),
+        (just_pressed.down, 0, -1),
+    ];
+    for &(_, kx, ky) in taps.iter().filter(|tap| tap.0) {
+        input.push(if is_shift {
+            ReplayFrame {
+                shot: Some(ShootingDir::new(kx, ky)),
+                ..Default::default()
             }
         } else {
-            let kx = (right | jp_right) - (left | jp_left);
-            let ky = (up | jp_up) - (down | jp_down);
-
-            let cur_dir = MovingDir::new(kx, ky);
-
-            robbo_dir.just_pressed =
-                robbo_dir.just_pressed || (jp_left | jp_right | jp_up | jp_down) > 0;
-
-            if !cur_dir.is_empty() || !robbo_dir.just_pressed {
-                robbo_dir.dir = cur_dir;
+            ReplayFrame {
+                dir: MovingDir::new(kx, ky),
+                ..Default::default()
             }
+        });
+    }
+
+    if bindings.just_pressed(&keyboard_input, Action::FireFacing) || gamepad.fire_facing {
+        // facing after the queued moves
+        let queued_dir = input
+            .queue
+            .iter()
+            .rev()
+            .map(|frame| frame.dir)
+            .find(|dir| !dir.is_empty());
+        let facing = queued_dir.map(|dir| (dir.x(), dir.y())).or_else(|| {
+            query
+                .iter_mut()
+                .next()
+                .and_then(|(_, _, tiles, _)| robbo_facing(&tiles))
+        });
+        if let Some((kx, ky)) = facing {
+            input.push(ReplayFrame {
+                shot: Some(ShootingDir::new(kx, ky)),
+                ..Default::default()
+            });
+        }
+    }
 
-            if *moving_dir != robbo_dir.dir {
-                *moving_dir = robbo_dir.dir;
-                *tiles = match (kx, ky) {
-                    (-1, 0) => Tiles::new(&[64, 65]),
-                    (1, 0) => Tiles::new(&[60, 61]),
-                    (0, -1) => Tiles::new(&[62, 63]),
-                    (0, 1) => Tiles::new(&[66, 67]),
-                    _ => Tiles::new(&tiles.tiles[0..1]),
-                }
+    if !frame_cnt.is_keyframe() {
+        return;
+    }
+    let held = if is_shift {
+        MovingDir::zero()
+    } else {
+        MovingDir::new(
+            pressed.right as i32 - pressed.left as i32,
+            pressed.up as i32 - pressed.down as i32,
+        )
+    };
+    let frame = input.queue.pop_front().unwrap_or(ReplayFrame {
+        dir: held,
+        ..Default::default()
+    });
+    input.applied = frame;
+    if frame.kill {
+        events.send(GameEvent::KillRobbo);
+    }
+    if frame.level_change != 0 {
+        events.send(GameEvent::ReloadLevel(frame.level_change));
+    }
+    for (entity, mut moving_dir, mut tiles, shooting_dir) in query.iter_mut() {
+        if let Some(shot) = frame.shot {
+            // the shot is fired by shot_system
+            if inventory.bullets > 0 && shooting_dir.is_none() {
+                inventory.bullets -= 1;
+                *moving_dir = MovingDir::zero();
+                commands.insert_one(entity, shot);
+                sounds.send(Sound::SHOT);
             }
+        } else if *moving_dir != frame.dir {
+            *moving_dir = frame.dir;
+            *tiles = robbo_tiles(frame.dir.x(), frame.dir.y(), &tiles);
         }
     }
 }
-pub fn robbo_dir_reset(mut robbo_dir: ResMut<RobboDir>, frame_cnt: Res<FrameCnt>) {
-    if frame_cnt.is_keyframe() {
-        robbo_dir.just_pressed = false;
+
+/// direction of the last move, as shown by Robbo's tiles set with `robbo_tiles`
+pub fn robbo_facing(tiles: &Tiles) -> Option<(i32, i32)> {
+    match tiles.tiles().first()? {
+        64 | 65 => Some((-1, 0)),
+        60 | 61 => Some((1, 0)),
+        62 | 63 => Some((0, -1)),
+        66 | 67 => Some((0, 1)),
+        _ => None,
     }
 }
 
-#[derive(Default, Debug)]
-pub struct RobboDir {
-    pub just_pressed: bool,
-    pub dir: MovingDir,
+pub fn robbo_tiles(kx: i32, ky: i32, tiles: &Tiles) -> Tiles {
+    match (kx, ky) {
+        (-1, 0) => Tiles::new(&[64, 65]),
+        (1, 0) => Tiles::new(&[60, 61]),
+        (0, -1) => Tiles::new(&[62, 63]),
+        (0, 1) => Tiles::new(&[66, 67]),
+        _ => Tiles::new(&tiles.tiles()[0..1]),
+    }
 }
@@ -1,11 +1,27 @@
+pub mod controls;
 pub mod frame_cnt;
 mod frame_limiter;
-mod keyboard;
-mod render;
+pub mod game_state;
+pub mod gamepad;
+pub mod keyboard;
+pub mod render;
+pub mod progress;
+pub mod quicksave;
+pub mod replay;
+pub mod rewind;
+pub mod simulation;
+pub mod touch;
 pub mod audio;
 
+pub use controls::ControlsPlugin;
 pub use frame_cnt::{FrameCnt, FrameCntPlugin};
 pub use frame_limiter::FrameLimiterPlugin;
+pub use game_state::{GameState, GameStatePlugin};
 pub use keyboard::KeyboardPlugin;
 pub use render::RenderPlugin;
+pub use progress::ProgressPlugin;
+pub use quicksave::QuickSavePlugin;
+pub use replay::ReplayPlugin;
+pub use rewind::RewindPlugin;
+pub use simulation::SimulationPlugin;
 pub use audio::AudioPlugin;
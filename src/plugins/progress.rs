@@ -0,0 +1,168 @@
+use crate::frame_cnt::FrameCnt;
+use crate::game_events::GameEvent;
+use crate::levels::LevelInfo;
+use crate::plugins::simulation::gameplay_stage;
+use crate::storage::Storage;
+use anyhow::anyhow;
+use bevy::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Player's progress on a single levelset (level numbers start from 1)
+#[derive(Debug, PartialEq, Clone)]
+pub struct Progress {
+    /// level to continue from
+    pub current_level: usize,
+    /// next level after the highest completed one
+    pub unlocked_level: usize,
+    /// completed levels with the lowest number of keyframes Robbo needed
+    pub best_ticks: BTreeMap<usize, usize>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            current_level: 1,
+            unlocked_level: 1,
+            best_ticks: BTreeMap::new(),
+        }
+    }
+}
+
+impl Progress {
+    pub fn parse(data: &str) -> anyhow::Result<Progress> {
+        let mut progress = Progress::default();
+        let mut lines = data.lines().enumerate();
+        let mut collecting_levels = false;
+        while let Some((n, line)) = lines.next() {
+            let mut value = || {
+                lines
+                    .next()
+                    .map(|(_, v)| v.trim())
+                    .ok_or_else(|| anyhow!("line {}: missing value of {}", n + 1, line))
+            };
+            match line.trim() {
+                "" => (),
+                "[current_level]" => progress.current_level = value()?.parse()?,
+                "[unlocked_level]" => progress.unlocked_level = value()?.parse()?,
+                "[completed]" => collecting_levels = true,
+                line if collecting_levels => {
+                    let mut it = line.split_whitespace().map(|v| v.parse::<usize>());
+                    match (it.next(), it.next(), it.next()) {
+                        (Some(level), Some(ticks), None) => {
+                            progress.best_ticks.insert(level?, ticks?);
+                        }
+                        _ => return Err(anyhow!("line {}: invalid level {:?}", n + 1, line)),
+                    }
+                }
+                line => return Err(anyhow!("line {}: unexpected {:?}", n + 1, line)),
+            }
+        }
+        Ok(progress)
+    }
+
+    /// default progress if nothing was saved yet (or it is unreadable)
+    pub fn load(storage: &Storage) -> Progress {
+        let data = match storage.read() {
+            Ok(Some(data)) => data,
+            Ok(None) => return Progress::default(),
+            Err(err) => {
+                warn!("cannot read progress from {}: {}", storage, err);
+                return Progress::default();
+            }
+        };
+        Progress::parse(&data).unwrap_or_else(|err| {
+            warn!("invalid progress in {}: {}", storage, err);
+            Progress::default()
+        })
+    }
+
+    pub fn save(&self, storage: &Storage) {
+        if let Err(err) = storage.write(&self.to_string()) {
+            warn!("cannot save progress into {}: {}", storage, err);
+        }
+    }
+
+    /// game continues from the next level (wrapping after the last one);
+    /// returns true if it is the best attempt so far
+    pub fn complete(&mut self, level: usize, ticks: usize) -> bool {
+        self.current_level = level + 1;
+        self.unlocked_level = self.unlocked_level.max(level + 1);
+        let best = self.best_ticks.entry(level).or_insert(ticks);
+        *best = (*best).min(ticks);
+        *best == ticks
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[current_level]\n{}\n[unlocked_level]\n{}\n[completed]\n",
+            self.current_level, self.unlocked_level
+        )?;
+        for (level, ticks) in &self.best_ticks {
+            writeln!(f, "{} {}", level, ticks)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct ProgressStorage(pub Storage);
+
+/// Saves progress whenever a level is started or completed
+pub struct ProgressPlugin {
+    pub storage: ProgressStorage,
+    pub progress: Progress,
+}
+
+impl Plugin for ProgressPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(self.storage.clone())
+            .add_resource(self.progress.clone())
+            // level is reloaded in "game_events" stage, completion must be seen before that
+            .add_stage_before("game_events", "progress", gameplay_stage())
+            .add_system_to_stage("progress", progress_system.system());
+    }
+}
+
+#[derive(Default)]
+pub struct ProgressState {
+    events: EventReader<GameEvent>,
+    start_frame: Option<usize>,
+}
+
+pub fn progress_system(
+    mut state: Local<ProgressState>,
+    (frame_cnt, level_info, game_events, storage): (
+        Res<FrameCnt>,
+        Res<LevelInfo>,
+        Res<Events<GameEvent>>,
+        Res<ProgressStorage>,
+    ),
+    mut progress: ResMut<Progress>,
+) {
+    if !frame_cnt.is_keyframe() || level_info.start_frame.is_none() {
+        return;
+    }
+    let level = level_info.current_level + 1;
+    let mut is_changed = false;
+    for event in state.events.iter(&game_events) {
+        if let GameEvent::LevelComplete = event {
+            let ticks = frame_cnt.ticks_since(level_info.start_frame.unwrap());
+            if progress.complete(level, ticks) {
+                info!("level {} completed in {} keyframes, best so far", level, ticks);
+            }
+            is_changed = true;
+        }
+    }
+    if state.start_frame != level_info.start_frame {
+        state.start_frame = level_info.start_frame;
+        progress.current_level = level;
+        is_changed = true;
+    }
+    if is_changed {
+        progress.save(&storage.0);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::plugins::controls::{Action, Bindings};
+use crate::plugins::rewind::History;
+use crate::plugins::GameState;
+use crate::snapshot::Snapshot;
+use crate::storage::Storage;
+use bevy::prelude::*;
+
+/// Full board snapshot of current levelset saved with F5 and loaded with F9 (by default).
+/// Works in any game state but controls editing, the board is saved at the end of frame.
+pub struct QuickSavePlugin {
+    pub storage: Storage,
+}
+
+pub struct QuickSaveStorage(pub Storage);
+
+impl Plugin for QuickSavePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(QuickSaveStorage(self.storage.clone()))
+            .add_stage_after("tick", "quicksave", SystemStage::parallel())
+            .add_system_to_stage("quicksave", quicksave_system.system());
+    }
+}
+
+fn quicksave_system(world: &mut World, resources: &mut Resources) {
+    if *resources.get::<State<GameState>>().unwrap().current() == GameState::Controls {
+        return;
+    }
+    let (save, load) = {
+        let keyboard_input = resources.get::<Input<KeyCode>>().unwrap();
+        let bindings = resources.get::<Bindings>().unwrap();
+        (
+            bindings.just_pressed(&keyboard_input, Action::QuickSave),
+            bindings.just_pressed(&keyboard_input, Action::QuickLoad),
+        )
+    };
+    let storage = &resources.get::<QuickSaveStorage>().unwrap().0;
+    if save {
+        let snapshot = Snapshot::capture_full(world, resources);
+        match storage.write(&snapshot.to_string()) {
+            Ok(()) => info!("board saved into {}", storage),
+            Err(err) => warn!("cannot save board into {}: {}", storage, err),
+        }
+    } else if load {
+        let snapshot = match storage.read() {
+            Ok(Some(data)) => Snapshot::parse(&data),
+            Ok(None) => {
+                info!("nothing saved in {}", storage);
+                return;
+            }
+            Err(err) => Err(err),
+        };
+        match snapshot {
+            Ok(snapshot) => {
+                snapshot.restore(world, resources);
+                if let Some(mut history) = resources.get_mut::<History>() {
+                    history.clear();
+                }
+                info!("board loaded from {}", storage);
+            }
+            Err(err) => warn!("cannot load board from {}: {:#}", storage, err),
+        }
+    }
+}
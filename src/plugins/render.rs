@@ -1,9 +1,14 @@
 use crate::components::prelude::*;
 use crate::consts::*;
 use crate::frame_cnt::FrameCnt;
+use crate::game_events::GameEvent;
 use crate::inventory::Inventory;
-use crate::levels::LevelInfo;
+use crate::levels::{LevelInfo, LevelSet};
+use crate::plugins::controls::ControlsEditor;
+use crate::plugins::touch::{update_touch_buttons, TouchControls, CONTROLS_HEIGHT};
+use crate::plugins::GameState;
 use bevy::render::camera::{OrthographicProjection, WindowOrigin};
+use bevy::render::pass::ClearColor;
 use bevy::sprite::TextureAtlas;
 use bevy::window::WindowResized;
 use bevy::{prelude::*, reflect::TypeUuid};
@@ -15,28 +20,70 @@ const TEXTURE_ATLAS_HANDLE: HandleUntyped =
 const DIGITS_ATLAS_HANDLE: HandleUntyped = 
     HandleUntyped::weak_from_u64(TextureAtlas::TYPE_UUID, 1);
 
+/// board cells scrolled per second when following Robbo is this times the remaining way
+const SCROLL_RATE: f32 = 8.0;
+/// status bar and touch controls are drawn above the mask, the mask above the board
+const BOARD_MASK_Z: f32 = 5.0;
+const STATUS_Z: f32 = 6.0;
+
 #[derive(Default)]
 pub struct RenderState {
     pub reader: EventReader<WindowResized>,
+    /// camera leaves room for touch controls
+    pub touch_controls: bool,
+    pub window_size: Option<(f32, f32)>,
+    /// viewport offset the camera was placed with
+    pub offset: Vec2,
+}
+
+/// Part of the board shown (`MAX_BOARD_WIDTH` x `MAX_BOARD_HEIGHT` cells), kept by the camera.
+/// Bigger levels scroll to follow Robbo.
+#[derive(Default, Debug)]
+pub struct Viewport {
+    /// scroll of the board in world units, zero for levels that fit
+    pub offset: Vec2,
+}
+
+/// Status bar, touch controls and overlay don't scroll with the board:
+/// they are kept at this translation relative to the viewport
+pub struct ScreenFixed(pub Vec3);
+
+/// hides board cells scrolled below the viewport, behind the status bar
+pub struct BoardMask;
+
+/// height of touch controls strip below the status bar
+fn controls_height(touch_controls: bool) -> f32 {
+    if touch_controls {
+        CONTROLS_HEIGHT
+    } else {
+        0.0
+    }
 }
 
-fn camera_scale(width: u32, height: u32) -> f32 {
+fn camera_scale(width: u32, height: u32, touch_controls: bool) -> f32 {
     let scale_x = (MAX_BOARD_WIDTH as f32 * 32.0) / (width as f32);
-    let scale_y = ((MAX_BOARD_HEIGHT + 2) as f32 * 32.0) / (height as f32);
+    let scale_y = ((MAX_BOARD_HEIGHT + 2) as f32 * 32.0 + controls_height(touch_controls))
+        / (height as f32);
     scale_x.max(scale_y)
 }
 
-fn camera_translation(width: u32, height: u32) -> Vec3 {
-    let scale = camera_scale(width, height);
+fn camera_translation(width: u32, height: u32, touch_controls: bool) -> Vec3 {
+    let scale = camera_scale(width, height, touch_controls);
     let board_width = MAX_BOARD_WIDTH as f32 * 32.0;
-    let board_height = (MAX_BOARD_HEIGHT + 2) as f32 * 32.0;
+    let board_height = (MAX_BOARD_HEIGHT + 2) as f32 * 32.0 + controls_height(touch_controls);
     Vec3::new(
         -16.0 - (width as f32 * scale - board_width) / 2.0,
-        -16.0 - (height as f32 * scale - board_height) / 2.0,
+        -16.0 - controls_height(touch_controls) - (height as f32 * scale - board_height) / 2.0,
         0.0,
     )
 }
 
+fn camera_transform(width: u32, height: u32, touch_controls: bool) -> Transform {
+    let scale = camera_scale(width, height, touch_controls);
+    Transform::from_translation(camera_translation(width, height, touch_controls))
+        .mul_transform(Transform::from_scale(Vec3::new(scale, scale, scale)))
+}
+
 fn spawn_counter<T>(
     commands: &mut Commands,
     component: T,
@@ -47,10 +94,11 @@ fn spawn_counter<T>(
     T: Send + Sync + Copy + 'static,
 {
     let color = Color::rgb(0.8, 0.8, 0.8);
+    let icon_translation = Vec3::new(x_offset as f32 * 16.0, 16.0, STATUS_Z);
     commands
         .spawn(SpriteSheetBundle {
             texture_atlas: TEXTURE_ATLAS_HANDLE.typed(),
-            transform: Transform::from_translation(Vec3::new(x_offset as f32 * 16.0, 16.0, 0.0)),
+            transform: Transform::from_translation(icon_translation),
             sprite: TextureAtlasSprite {
                 index: icon_index,
                 color: color,
@@ -58,17 +106,18 @@ fn spawn_counter<T>(
             },
             ..Default::default()
         })
-        .with(StatusOffset(x_offset));
+        .with_bundle((StatusOffset(x_offset), ScreenFixed(icon_translation)));
 
     for k in 0..n_digits {
+        let translation = Vec3::new(
+            (((x_offset + n_digits - k - 1) * 16) + 32 - 8) as f32,
+            16.0,
+            STATUS_Z,
+        );
         commands
             .spawn(SpriteSheetBundle {
                 texture_atlas: DIGITS_ATLAS_HANDLE.typed(),
-                transform: Transform::from_translation(Vec3::new(
-                    (((x_offset + n_digits - k - 1) * 16) + 32 - 8) as f32,
-                    16.0,
-                    0.0,
-                )),
+                transform: Transform::from_translation(translation),
                 sprite: TextureAtlasSprite {
                     index: 8,
                     color: color,
@@ -80,6 +129,7 @@ fn spawn_counter<T>(
                 component,
                 Digit(k),
                 StatusOffset(x_offset + n_digits - k + 1),
+                ScreenFixed(translation),
             ));
     }
 }
@@ -99,8 +149,10 @@ where
 
 pub fn render_setup(
     commands: &mut Commands,
+    inventory: Res<Inventory>,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     let texture_handle = asset_server.load("icons32.png");
     let texture_atlas = TextureAtlas::from_grid(texture_handle, Vec2::new(32.0, 32.0), 12, 8);
@@ -109,8 +161,6 @@ pub fn render_setup(
     let box_size = 32.0;
     let width = MAX_BOARD_WIDTH as f32 * box_size;
     let height = (MAX_BOARD_HEIGHT + 2) as f32 * box_size;
-    let scale = camera_scale(width as u32, height as u32);
-    let translation = camera_translation(width as u32, height as u32);
     commands.spawn(Camera2dBundle {
         orthographic_projection: OrthographicProjection {
             bottom: 0.0,
@@ -120,36 +170,134 @@ pub fn render_setup(
             window_origin: WindowOrigin::BottomLeft,
             ..Default::default()
         },
-        transform: Transform::from_translation(translation)
-            .mul_transform(Transform::from_scale(Vec3::new(scale, scale, scale))),
+        transform: camera_transform(width as u32, height as u32, false),
         ..Default::default()
-    });
+    })
+    .with(Viewport::default());
+    // from the bottom of the touch controls up to the first board row
+    let mask_height = 2.0 * box_size + CONTROLS_HEIGHT + box_size / 2.0;
+    let mask_center = Vec3::new(
+        width / 2.0 - box_size / 2.0,
+        1.5 * box_size - mask_height / 2.0,
+        BOARD_MASK_Z,
+    );
+    commands
+        .spawn(SpriteBundle {
+            material: materials.add(default_background().into()),
+            // wider than the viewport: covers margins of wide windows too
+            sprite: Sprite::new(Vec2::new(3.0 * width, mask_height)),
+            transform: Transform::from_translation(mask_center),
+            ..Default::default()
+        })
+        .with_bundle((BoardMask, ScreenFixed(mask_center)));
 
     {
         let digits_handle = asset_server.load::<Texture, _>("digits2.png");
         let digits_atlas = TextureAtlas::from_grid(digits_handle, Vec2::new(16.0, 32.0), 10, 1);
         texture_atlases.set_untracked(DIGITS_ATLAS_HANDLE, digits_atlas);
 
-        let offs = (62 - 22) / 2;
-        spawn_counter(commands, ScrewCounter, offs + 0, 2, 83);
-        spawn_counter(commands, KeyCounter, offs + 6, 2, 95);
-        spawn_counter(commands, AmmoCounter, offs + 12, 2, 91);
-        spawn_counter(commands, LevelNumber, offs + 18, 2, 71);
+        if inventory.lives.is_some() {
+            let offs = (62 - 28) / 2;
+            spawn_counter(commands, LivesCounter, offs, 2, 60);
+            spawn_counter(commands, ScrewCounter, offs + 6, 2, 83);
+            spawn_counter(commands, KeyCounter, offs + 12, 2, 95);
+            spawn_counter(commands, AmmoCounter, offs + 18, 2, 91);
+            spawn_counter(commands, LevelNumber, offs + 24, 2, 71);
+        } else {
+            let offs = (62 - 22) / 2;
+            spawn_counter(commands, ScrewCounter, offs + 0, 2, 83);
+            spawn_counter(commands, KeyCounter, offs + 6, 2, 95);
+            spawn_counter(commands, AmmoCounter, offs + 12, 2, 91);
+            spawn_counter(commands, LevelNumber, offs + 18, 2, 71);
+        }
+    }
+}
+
+/// scroll showing the last rows and columns of the level
+fn max_viewport_offset(level_size: (i32, i32)) -> Vec2 {
+    Vec2::new(
+        (level_size.0 - MAX_BOARD_WIDTH).max(0) as f32,
+        (level_size.1 - MAX_BOARD_HEIGHT).max(0) as f32,
+    ) * 32.0
+}
+
+/// offset showing `focus` (board cell) in the middle of the viewport, clamped to the level
+pub fn viewport_offset(focus: (i32, i32), level_size: (i32, i32)) -> Vec2 {
+    let centered = Vec2::new(
+        focus.0 as f32 + 0.5 - MAX_BOARD_WIDTH as f32 / 2.0,
+        focus.1 as f32 + 0.5 - MAX_BOARD_HEIGHT as f32 / 2.0,
+    ) * 32.0;
+    centered.max(Vec2::zero()).min(max_viewport_offset(level_size))
+}
+
+/// Scrolls smoothly towards Robbo, jumps there when a level is (re)built.
+/// While Robbo is missing (respawn animation) it follows the place where he appears,
+/// or stays where it is.
+pub fn scroll_viewport(
+    mut shown_level: Local<Option<usize>>,
+    (time, level_info): (Res<Time>, Res<LevelInfo>),
+    robbos: Query<&Position, With<Robbo>>,
+    animations: Query<(&Position, &Animation)>,
+    mut viewports: Query<&mut Viewport>,
+) {
+    let focus = robbos.iter().next().copied().or_else(|| {
+        animations.iter().find_map(|(pos, animation)| match animation.0 {
+            Some(GameEvent::SpawnRobbo(_)) | Some(GameEvent::PreSpawnRobbo(_)) => Some(*pos),
+            _ => None,
+        })
+    });
+    let is_new_level = *shown_level != level_info.start_frame;
+    for mut viewport in viewports.iter_mut() {
+        let level_size = (level_info.width, level_info.height);
+        let target = match focus {
+            Some(pos) => viewport_offset(pos.as_tuple(), level_size),
+            None => viewport.offset.min(max_viewport_offset(level_size)),
+        };
+        let way = target - viewport.offset;
+        viewport.offset = if (is_new_level && focus.is_some()) || way.length() < 0.5 {
+            target
+        } else {
+            viewport.offset + way * (time.delta_seconds() * SCROLL_RATE).min(1.0)
+        };
+    }
+    if focus.is_some() {
+        *shown_level = level_info.start_frame;
     }
 }
 
 pub fn update_camera(
     mut state: ResMut<RenderState>,
-    events: Res<Events<WindowResized>>,
-    mut items: Query<(&mut Transform, &OrthographicProjection)>,
+    (events, windows, touch_controls): (
+        Res<Events<WindowResized>>,
+        Res<Windows>,
+        Res<TouchControls>,
+    ),
+    mut cameras: Query<(&mut Transform, &Viewport)>,
+    mut screen_fixed: Query<(&mut Transform, &ScreenFixed)>,
 ) {
     let event: Option<WindowResized> = state.reader.iter(&events).cloned().last();
-    if let Some(event) = event {
-        for (mut transform, _) in items.iter_mut() {
-            let scale = camera_scale(event.width as u32, event.height as u32);
-            let translation = camera_translation(event.width as u32, event.height as u32);
-            *transform = Transform::from_translation(translation)
-                .mul_transform(Transform::from_scale(Vec3::new(scale, scale, scale)));
+    let is_toggled = state.touch_controls != touch_controls.enabled;
+    state.touch_controls = touch_controls.enabled;
+    if let Some(event) = event.as_ref() {
+        state.window_size = Some((event.width, event.height));
+    } else if state.window_size.is_none() || is_toggled {
+        // touch controls were just shown
+        state.window_size = windows
+            .get_primary()
+            .map(|window| (window.width(), window.height()));
+    }
+    for (mut transform, viewport) in cameras.iter_mut() {
+        let is_changed = event.is_some() || is_toggled || viewport.offset != state.offset;
+        if let (true, Some((width, height))) = (is_changed, state.window_size) {
+            *transform = camera_transform(width as u32, height as u32, state.touch_controls);
+            transform.translation += viewport.offset.extend(0.0);
+            state.offset = viewport.offset;
+        }
+    }
+    let offset = state.offset.extend(0.0);
+    for (mut transform, fixed) in screen_fixed.iter_mut() {
+        if transform.translation != fixed.0 + offset {
+            transform.translation = fixed.0 + offset;
         }
     }
 }
@@ -161,12 +309,161 @@ pub fn update_status_bar(
     mut screw_digits: Query<(&Digit, &mut TextureAtlasSprite), With<ScrewCounter>>,
     mut key_digits: Query<(&Digit, &mut TextureAtlasSprite), With<KeyCounter>>,
     mut ammo_digits: Query<(&Digit, &mut TextureAtlasSprite), With<AmmoCounter>>,
+    mut lives_digits: Query<(&Digit, &mut TextureAtlasSprite), With<LivesCounter>>,
 ) {
     let screws_left = (level_info.screws - inventory.screws).max(0);
     set_digits(&mut level_digits, (level_info.current_level + 1) as u32);
     set_digits(&mut screw_digits, screws_left as u32);
     set_digits(&mut key_digits, inventory.keys as u32);
     set_digits(&mut ammo_digits, inventory.bullets as u32);
+    set_digits(&mut lives_digits, inventory.lives.unwrap_or(0) as u32);
+}
+
+/// shows levelset name, level author, level notes and game state prompt in the window title
+/// (level notes also in `#notes` element of the web page), logs them at level start
+pub fn update_level_title(
+    mut shown: Local<(Option<usize>, Option<GameState>, Option<String>)>,
+    level_info: Res<LevelInfo>,
+    level_sets: Res<Assets<LevelSet>>,
+    (game_state, controls_editor): (Res<State<GameState>>, Res<ControlsEditor>),
+    mut windows: ResMut<Windows>,
+) {
+    let current = (
+        level_info.start_frame,
+        Some(*game_state.current()),
+        controls_editor.prompt.clone(),
+    );
+    if *shown == current {
+        return;
+    }
+    let is_new_level = shown.0 != current.0;
+    *shown = current;
+    let level_set = level_sets.get(&level_info.level_set_handle);
+    let level = level_set.and_then(|level_set| level_set.get(level_info.current_level));
+    let mut title = String::from("Robbo");
+    if let Some(name) = level_set.and_then(|level_set| level_set.name.as_ref()) {
+        title += &format!(" - {}", name);
+    }
+    if let Some(level) = level {
+        title += &format!(" - level {}", level.number);
+        if let Some(author) = level.author.as_ref() {
+            title += &format!(" by {}", author);
+        }
+        if is_new_level {
+            info!("{}", title);
+            for line in &level.notes {
+                info!("{}", line);
+            }
+            #[cfg(target_arch = "wasm32")]
+            show_notes_on_page(&level.notes);
+        }
+        if !level.notes.is_empty() {
+            title += &format!(" - {}", level.notes.join(" "));
+        }
+    }
+    if let Some(prompt) = shown.2.as_deref().or_else(|| game_state.prompt()) {
+        title += &format!(" - {}", prompt);
+    }
+    if let Some(window) = windows.get_primary_mut() {
+        window.set_title(title);
+    }
+}
+
+/// winit doesn't show the window title on the web page
+#[cfg(target_arch = "wasm32")]
+fn show_notes_on_page(notes: &[String]) {
+    let element = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id("notes"));
+    if let Some(element) = element {
+        element.set_text_content(Some(&notes.join("\n")));
+    }
+}
+
+/// background while the levelset is loading or when it has no valid colours
+fn default_background() -> Color {
+    Color::rgb(0.3, 0.3, 0.5)
+}
+
+/// level [colour] as the background, set whenever a level is (re)built
+pub fn update_background(
+    mut shown: Local<Option<(usize, Option<usize>)>>,
+    level_info: Res<LevelInfo>,
+    level_sets: Res<Assets<LevelSet>>,
+    (mut clear_color, mut materials): (ResMut<ClearColor>, ResMut<Assets<ColorMaterial>>),
+    masks: Query<&Handle<ColorMaterial>, With<BoardMask>>,
+) {
+    let current = (level_info.current_level, level_info.start_frame);
+    if *shown == Some(current) {
+        return;
+    }
+    let level_set = match level_sets.get(&level_info.level_set_handle) {
+        Some(level_set) => level_set,
+        None => return,
+    };
+    *shown = Some(current);
+    if let Some(level) = level_set.get(level_info.current_level) {
+        clear_color.0 = level_set
+            .level_color(level)
+            .unwrap_or_else(default_background);
+        for material in masks.iter() {
+            if let Some(material) = materials.get_mut(material) {
+                material.color = clear_color.0;
+            }
+        }
+    }
+}
+
+pub fn update_overlay(
+    commands: &mut Commands,
+    mut shown: Local<Option<GameState>>,
+    game_state: Res<State<GameState>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    overlays: Query<Entity, With<Overlay>>,
+) {
+    let current = *game_state.current();
+    if *shown == Some(current) {
+        return;
+    }
+    *shown = Some(current);
+    for entity in overlays.iter() {
+        commands.despawn(entity);
+    }
+    let icon = match current {
+        GameState::Playing => return,
+        GameState::Paused | GameState::Controls => None,
+        GameState::Title => Some(60),
+        GameState::LevelComplete => Some(17),
+        GameState::GameOver => Some(85),
+    };
+    let box_size = 32.0;
+    let center = Vec3::new(
+        (MAX_BOARD_WIDTH - 1) as f32 * box_size / 2.0,
+        2.0 * box_size + (MAX_BOARD_HEIGHT - 1) as f32 * box_size / 2.0,
+        10.0,
+    );
+    commands
+        .spawn(SpriteBundle {
+            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.6).into()),
+            sprite: Sprite::new(Vec2::new(
+                MAX_BOARD_WIDTH as f32 * box_size,
+                MAX_BOARD_HEIGHT as f32 * box_size,
+            )),
+            transform: Transform::from_translation(center),
+            ..Default::default()
+        })
+        .with_bundle((Overlay, ScreenFixed(center)));
+    if let Some(icon) = icon {
+        commands
+            .spawn(SpriteSheetBundle {
+                texture_atlas: TEXTURE_ATLAS_HANDLE.typed(),
+                sprite: TextureAtlasSprite::new(icon),
+                transform: Transform::from_translation(center + Vec3::new(0.0, 0.0, 1.0))
+                    .mul_transform(Transform::from_scale(Vec3::new(4.0, 4.0, 1.0))),
+                ..Default::default()
+            })
+            .with_bundle((Overlay, ScreenFixed(center + Vec3::new(0.0, 0.0, 1.0))));
+    }
 }
 
 pub fn create_sprites(
@@ -185,9 +482,11 @@ pub fn create_sprites(
     }
 }
 
+/// Moves sprites towards board positions, so that they arrive by the next keyframe.
+/// Progress is measured in time (see `FrameCnt::keyframe_progress`), not in rendered frames.
 pub fn prepare_render(
+    mut last_progress: Local<f32>,
     frame_cnt: Res<FrameCnt>,
-    opts: Res<crate::Opts>,
     mut items: Query<(
         Entity,
         &Position,
@@ -203,30 +502,35 @@ pub fn prepare_render(
         .chain(smooth_update_items2.iter().into_iter())
         .collect();
     let box_size = 32.0;
-    let min_step = box_size / (opts.key_frame_interval as f32) * 1.01;
+    let progress = frame_cnt.keyframe_progress();
+    // progress went back: next keyframe has started
+    let last = if progress >= *last_progress {
+        *last_progress
+    } else {
+        0.0
+    };
+    *last_progress = progress;
+    // part of the remaining way to cover in this frame
+    let time_left = 1.0 - last;
+    let fraction = (progress - last) / time_left;
     let trans = Vec3::new(0.0, 2.0 * box_size, 0.0);
     for (entity, position, tiles, mut transform, mut sprite) in items.iter_mut() {
         let dest = trans + Vec3::new(position.x() as f32, position.y() as f32, 0.0) * box_size;
         let cur = transform.translation;
         if cur != dest {
             if to_smooth_update.contains(&entity) {
-                let steps_left = (opts.key_frame_interval
-                    - ((frame_cnt.value()) % opts.key_frame_interval))
-                    as f32;
-                let step = (dest - cur) / steps_left;
-                if step.x.abs() > 0.01 || step.y.abs() > 0.01 {
-                    let dest = if step.x.abs() <= min_step && step.y.abs() <= min_step {
-                        cur + step
-                    } else {
-                        dest
-                    };
+                let way = dest - cur;
+                // farther than a single step: teleported, shown at once
+                let is_step = way.x.abs().max(way.y.abs()) <= box_size * time_left * 1.01;
+                let dest = if is_step { cur + way * fraction } else { dest };
+                if (dest - cur).abs().max_element() > 0.01 || !is_step {
                     *transform = Transform::from_translation(dest);
                 }
             } else {
                 *transform = Transform::from_translation(dest);
             }
         }
-        let sprite_index = tiles.tiles[tiles.current];
+        let sprite_index = tiles.tiles()[tiles.current];
         if sprite.index != sprite_index {
             sprite.index = sprite_index;
         }
@@ -235,19 +539,27 @@ pub fn prepare_render(
 
 pub struct RenderPlugin {
     pub vsync: bool,
+    /// show touch controls from the start (they also appear on first touch)
+    pub touch_controls: bool,
 }
 
 impl Plugin for RenderPlugin {
     fn build(&self, app: &mut AppBuilder) {
         let builder = app
-            .add_resource(bevy::render::pass::ClearColor(Color::rgb(0.3, 0.3, 0.5)))
+            .add_resource(ClearColor(default_background()))
             .add_resource(RenderState::default())
+            .add_resource(TouchControls::new(self.touch_controls))
             .add_startup_system(render_setup.system())
             .add_stage_before(
                 stage::POST_UPDATE,
                 "create_sprites",
                 SystemStage::parallel(),
             )
+            .add_stage_before(
+                stage::POST_UPDATE,
+                "scroll_viewport",
+                SystemStage::parallel(),
+            )
             .add_stage_before(stage::POST_UPDATE, "update_camera", SystemStage::parallel())
             .add_stage_before(
                 stage::POST_UPDATE,
@@ -255,9 +567,15 @@ impl Plugin for RenderPlugin {
                 SystemStage::parallel(),
             )
             .add_system_to_stage("create_sprites", create_sprites.system())
+            .add_system_to_stage("scroll_viewport", scroll_viewport.system())
             .add_system_to_stage("update_camera", update_camera.system())
             .add_system_to_stage("prepare_render", prepare_render.system());
 
-        builder.add_system_to_stage("prepare_render", update_status_bar.system());
+        builder
+            .add_system_to_stage("prepare_render", update_status_bar.system())
+            .add_system_to_stage("prepare_render", update_level_title.system())
+            .add_system_to_stage("prepare_render", update_background.system())
+            .add_system_to_stage("prepare_render", update_overlay.system())
+            .add_system_to_stage("prepare_render", update_touch_buttons.system());
     }
 }
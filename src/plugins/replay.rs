@@ -0,0 +1,312 @@
+use crate::components::prelude::*;
+use crate::frame_cnt::FrameCnt;
+use crate::game_events::GameEvent;
+use crate::inventory::Inventory;
+use crate::levels::LevelInfo;
+use crate::plugins::audio::Sound;
+use crate::plugins::keyboard::{robbo_tiles, InputQueue};
+use crate::plugins::simulation::gameplay_stage;
+use crate::resources::GameRng;
+use anyhow::{anyhow, Context};
+use bevy::prelude::*;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Player's input applied at single keyframe
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct ReplayFrame {
+    pub dir: MovingDir,
+    pub shot: Option<ShootingDir>,
+    pub kill: bool,
+    pub level_change: i32,
+}
+
+impl fmt::Display for ReplayFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.dir.x(), self.dir.y())?;
+        if let Some(shot) = self.shot {
+            write!(f, " shot:{}.{}", shot.x(), shot.y())?;
+        }
+        if self.kill {
+            write!(f, " kill")?;
+        }
+        if self.level_change != 0 {
+            write!(f, " level:{}", self.level_change)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_dir(s: &str) -> anyhow::Result<(i32, i32)> {
+    let mut it = s.split('.').map(|v| v.parse::<i32>());
+    match (it.next(), it.next(), it.next()) {
+        (Some(x), Some(y), None) => Ok((x?, y?)),
+        _ => Err(anyhow!("invalid direction: {:?}", s)),
+    }
+}
+
+impl FromStr for ReplayFrame {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> anyhow::Result<Self> {
+        let mut parts = line.split_whitespace();
+        let (kx, ky) = parse_dir(parts.next().unwrap_or(""))?;
+        let mut frame = ReplayFrame {
+            dir: MovingDir::new(kx, ky),
+            ..Default::default()
+        };
+        for part in parts {
+            if part == "kill" {
+                frame.kill = true;
+            } else if let Some(dir) = part.strip_prefix("shot:") {
+                let (kx, ky) = parse_dir(dir)?;
+                frame.shot = Some(ShootingDir::new(kx, ky));
+            } else if let Some(k) = part.strip_prefix("level:") {
+                frame.level_change = k.parse()?;
+            } else {
+                return Err(anyhow!("unknown replay token: {:?}", part));
+            }
+        }
+        Ok(frame)
+    }
+}
+
+/// Recorded attempt of a single level: everything needed to play it back identically
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Replay {
+    pub levelset_path: PathBuf,
+    pub level: usize,
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> anyhow::Result<Replay> {
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read replay {:?}", path))?;
+        Replay::parse(&data).with_context(|| format!("invalid replay {:?}", path))
+    }
+
+    pub fn parse(data: &str) -> anyhow::Result<Replay> {
+        let mut replay = Replay::default();
+        let mut lines = data.lines().enumerate();
+        let mut collecting_frames = false;
+        while let Some((n, line)) = lines.next() {
+            let mut value = || {
+                lines
+                    .next()
+                    .map(|(_, v)| v.trim())
+                    .ok_or_else(|| anyhow!("line {}: missing value of {}", n + 1, line))
+            };
+            match line.trim() {
+                "" => (),
+                "[levelset]" => replay.levelset_path = value()?.into(),
+                "[level]" => replay.level = value()?.parse()?,
+                "[seed]" => replay.seed = value()?.parse()?,
+                "[frames]" => collecting_frames = true,
+                line if collecting_frames => replay.frames.push(
+                    line.parse()
+                        .with_context(|| format!("line {}", n + 1))?,
+                ),
+                line => return Err(anyhow!("line {}: unexpected {:?}", n + 1, line)),
+            }
+        }
+        Ok(replay)
+    }
+
+    /// everything but frames, these are appended one per line
+    pub fn header(&self) -> String {
+        format!(
+            "[levelset]\n{}\n[level]\n{}\n[seed]\n{}\n[frames]\n",
+            self.levelset_path.display(),
+            self.level,
+            self.seed
+        )
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header())?;
+        for frame in &self.frames {
+            writeln!(f, "{}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+pub enum ReplayPlugin {
+    /// write every level attempt to a file (overwritten when level restarts)
+    Record(PathBuf),
+    /// feed the replay's frames instead of keyboard input
+    Play(Replay),
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        match self {
+            ReplayPlugin::Record(path) => {
+                app.add_resource(Recorder {
+                    path: path.clone(),
+                    file: None,
+                    start_frame: None,
+                    replay: Replay::default(),
+                    frame_cnts: vec![],
+                })
+                .add_stage_after("keyboard", "replay", gameplay_stage())
+                .add_system_to_stage("replay", record_system.system());
+            }
+            ReplayPlugin::Play(replay) => {
+                app.add_resource(Player {
+                    replay: replay.clone(),
+                    cursor: 0,
+                    start_frame: None,
+                    is_finished: false,
+                })
+                .add_stage_after(stage::EVENT, "replay", gameplay_stage())
+                .add_system_to_stage("replay", replay_system.system());
+            }
+        }
+    }
+}
+
+pub struct Recorder {
+    path: PathBuf,
+    file: Option<File>,
+    start_frame: Option<usize>,
+    replay: Replay,
+    /// frame counter of every recorded frame, to drop frames undone by rewinding
+    frame_cnts: Vec<usize>,
+}
+
+impl Recorder {
+    /// (re)writes the whole replay
+    fn start(&mut self) -> std::io::Result<()> {
+        let mut file = File::create(&self.path)?;
+        write!(file, "{}", self.replay)?;
+        self.file = Some(file);
+        Ok(())
+    }
+    fn push(&mut self, frame_cnt: usize, frame: ReplayFrame) -> std::io::Result<()> {
+        let kept = self.frame_cnts.iter().take_while(|&&cnt| cnt < frame_cnt).count();
+        let is_rewound = kept < self.frame_cnts.len();
+        self.frame_cnts.truncate(kept);
+        self.replay.frames.truncate(kept);
+        self.frame_cnts.push(frame_cnt);
+        self.replay.frames.push(frame);
+        if is_rewound {
+            return self.start();
+        }
+        match self.file.as_mut() {
+            Some(file) => writeln!(file, "{}", frame),
+            None => Ok(()),
+        }
+    }
+}
+
+pub fn record_system(
+    mut recorder: ResMut<Recorder>,
+    (frame_cnt, level_info, rng, input, opts): (
+        Res<FrameCnt>,
+        Res<LevelInfo>,
+        Res<GameRng>,
+        Res<InputQueue>,
+        Res<crate::Opts>,
+    ),
+    robbo: Query<(&MovingDir, Option<&ShootingDir>), With<Robbo>>,
+) {
+    if !frame_cnt.is_keyframe() || level_info.start_frame.is_none() {
+        return;
+    }
+    if recorder.start_frame != level_info.start_frame {
+        recorder.start_frame = level_info.start_frame;
+        recorder.replay = Replay {
+            levelset_path: opts.levelset_path.clone(),
+            level: level_info.current_level + 1,
+            seed: rng.seed(),
+            frames: vec![],
+        };
+        recorder.frame_cnts.clear();
+        info!("recording level {} into {:?}", recorder.replay.level, recorder.path);
+        if let Err(err) = recorder.start() {
+            warn!("cannot create replay file {:?}: {}", recorder.path, err);
+            recorder.file = None;
+        }
+    }
+    let (dir, shot) = robbo
+        .iter()
+        .next()
+        .map(|(dir, shot)| (*dir, shot.cloned()))
+        .unwrap_or_default();
+    let frame = ReplayFrame {
+        dir,
+        shot,
+        kill: input.applied.kill,
+        level_change: input.applied.level_change,
+    };
+    if let Err(err) = recorder.push(frame_cnt.value(), frame) {
+        warn!("cannot write replay file {:?}: {}", recorder.path, err);
+        recorder.file = None;
+    }
+}
+
+pub struct Player {
+    replay: Replay,
+    cursor: usize,
+    start_frame: Option<usize>,
+    is_finished: bool,
+}
+
+pub fn replay_system(
+    commands: &mut Commands,
+    mut player: ResMut<Player>,
+    (frame_cnt, level_info, mut events, mut inventory, mut sounds): (
+        Res<FrameCnt>,
+        Res<LevelInfo>,
+        ResMut<Events<GameEvent>>,
+        ResMut<Inventory>,
+        ResMut<Events<Sound>>,
+    ),
+    mut robbo: Query<(Entity, &mut MovingDir, &mut Tiles, Option<&ShootingDir>), With<Robbo>>,
+) {
+    if !frame_cnt.is_keyframe() || level_info.start_frame.is_none() || player.is_finished {
+        return;
+    }
+    if player.start_frame.is_none() {
+        player.start_frame = level_info.start_frame;
+    }
+    // only the recorded attempt is played, level restart ends the replay
+    let frame = match player.replay.frames.get(player.cursor) {
+        Some(frame) if player.start_frame == level_info.start_frame => *frame,
+        _ => {
+            info!("replay finished after {} keyframes", player.cursor);
+            player.is_finished = true;
+            return;
+        }
+    };
+    player.cursor += 1;
+
+    if frame.kill {
+        events.send(GameEvent::KillRobbo);
+    }
+    if frame.level_change != 0 {
+        events.send(GameEvent::ReloadLevel(frame.level_change));
+    }
+    for (entity, mut moving_dir, mut tiles, shooting_dir) in robbo.iter_mut() {
+        if *moving_dir != frame.dir {
+            *moving_dir = frame.dir;
+            *tiles = robbo_tiles(frame.dir.x(), frame.dir.y(), &tiles);
+        }
+        // same rules as for the keyboard, a replay can't shoot without ammo
+        if let Some(shot) = frame.shot {
+            if inventory.bullets > 0 && shooting_dir.is_none() {
+                inventory.bullets -= 1;
+                commands.insert_one(entity, shot);
+                sounds.send(Sound::SHOT);
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::plugins::controls::{Action, Bindings};
+use crate::plugins::simulation::gameplay_stage;
+use crate::plugins::FrameCnt;
+use crate::snapshot::Snapshot;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+/// keyframes kept for rewinding (~13s at default speed)
+const MAX_SNAPSHOTS: usize = 100;
+
+/// Board snapshots of the recent keyframes of current level attempt
+#[derive(Default)]
+pub struct History {
+    snapshots: VecDeque<Snapshot>,
+    /// step back by one keyframe at the next keyframe
+    pub rewind: bool,
+}
+
+impl History {
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+}
+
+/// Ring buffer of keyframe snapshots, rewinding restores the world to an earlier keyframe.
+/// Rewind requests are set by `rewind_keyboard` (or directly in `History`).
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(History::default())
+            .add_stage_after("tick", "rewind", gameplay_stage())
+            .add_system_to_stage("rewind", rewind_system.system());
+    }
+}
+
+/// Runs at the end of frame, when the board is ready for the next keyframe.
+/// Rewinding drops snapshot of the previous keyframe and restores the one before,
+/// so holding the rewind key steps back by one keyframe per keyframe.
+fn rewind_system(world: &mut World, resources: &mut Resources) {
+    if !resources.get::<FrameCnt>().unwrap().is_keyframe() {
+        return;
+    }
+    let mut history = resources.get_mut::<History>().unwrap();
+    if history.rewind && history.snapshots.len() >= 2 {
+        history.snapshots.pop_back();
+        let snapshot = history.snapshots.back().unwrap();
+        info!("rewind to frame {}", snapshot.frame);
+        snapshot.restore(world, resources);
+        return;
+    }
+    let snapshot = Snapshot::capture(world, resources);
+    // history doesn't span level (re)starts
+    let is_restarted = matches!(
+        history.snapshots.back(),
+        Some(last) if last.level_start_frame() != snapshot.level_start_frame()
+    );
+    if is_restarted {
+        history.snapshots.clear();
+    }
+    if history.snapshots.len() == MAX_SNAPSHOTS {
+        history.snapshots.pop_front();
+    }
+    history.snapshots.push_back(snapshot);
+}
+
+pub fn rewind_keyboard(
+    (keyboard_input, bindings): (Res<Input<KeyCode>>, Res<Bindings>),
+    mut history: ResMut<History>,
+) {
+    history.rewind = bindings.pressed(&keyboard_input, Action::Rewind);
+}
@@ -0,0 +1,170 @@
+use crate::game_events::GameEvent;
+use crate::inventory::Inventory;
+use crate::levels::{LevelInfo, LevelSet, LevelSetLoader};
+use crate::plugins::audio::Sound;
+use crate::plugins::game_state::{run_if_playing, GameState};
+use crate::plugins::replay::Replay;
+use crate::plugins::{FrameCntPlugin, ReplayPlugin};
+use crate::resources::{DamageMap, GameRng, ProcessedGameEvents};
+use crate::systems::*;
+use bevy::prelude::*;
+
+/// Gameplay rules only: stages, systems and resources needed to advance the board.
+/// Doesn't depend on window, input or rendering, so it may be driven headless
+/// (only `CorePlugin` and `AssetPlugin` are required).
+/// Gameplay stages run only in `GameState::Playing`, which is the initial state.
+pub struct SimulationPlugin {
+    key_frame_interval: usize,
+    seed: u64,
+    real_time: bool,
+}
+
+impl SimulationPlugin {
+    pub fn new(key_frame_interval: usize, seed: u64) -> SimulationPlugin {
+        SimulationPlugin {
+            key_frame_interval,
+            seed,
+            real_time: false,
+        }
+    }
+
+    /// keyframes follow the clock, not the number of updates (see `FrameCnt::advance`)
+    pub fn with_real_time(self) -> SimulationPlugin {
+        SimulationPlugin {
+            real_time: true,
+            ..self
+        }
+    }
+}
+
+impl Default for SimulationPlugin {
+    fn default() -> Self {
+        SimulationPlugin::new(8, rand::random())
+    }
+}
+
+/// stage that runs only while playing
+pub fn gameplay_stage() -> SystemStage {
+    SystemStage::parallel().with_run_criteria(run_if_playing.system())
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(Inventory::default())
+            .add_resource(State::new(GameState::Playing))
+            .add_resource(LevelInfo::default())
+            .add_resource(DamageMap::default())
+            .add_resource(GameRng::new(self.seed))
+            .add_resource(ProcessedGameEvents::default())
+            .add_resource(Events::<GameEvent>::default())
+            .add_event::<Sound>()
+            .add_asset::<LevelSet>()
+            .init_asset_loader::<LevelSetLoader>()
+            .add_plugin(if self.real_time {
+                FrameCntPlugin::new(self.key_frame_interval).with_real_time()
+            } else {
+                FrameCntPlugin::new(self.key_frame_interval)
+            })
+            .add_stage_after(
+                stage::EVENT,
+                "game_state",
+                StateStage::<GameState>::default(),
+            )
+            .add_stage_before(stage::EVENT, "update_game_events", gameplay_stage())
+            .add_stage_before(stage::PRE_UPDATE, "magnetic_field", gameplay_stage())
+            .add_stage_before(stage::UPDATE, "move", gameplay_stage())
+            .add_stage_before(stage::UPDATE, "move_robbo", gameplay_stage())
+            .add_stage_before(stage::POST_UPDATE, "reload_level", gameplay_stage())
+            .add_stage_before(stage::POST_UPDATE, "shots", gameplay_stage())
+            .add_stage_before(stage::POST_UPDATE, "process_damage", gameplay_stage())
+            .add_stage_before(stage::POST_UPDATE, "game_events", gameplay_stage())
+            .add_stage_after("game_events", "count_game_events", gameplay_stage())
+            .add_stage_after("frame_cnt", "tick", gameplay_stage())
+            .add_system_to_stage("update_game_events", update_game_events.system())
+            .add_system_to_stage("magnetic_field", magnetic_field_system.system())
+            .add_system_to_stage("process_damage", process_damage.system())
+            .add_system_to_stage("move", move_laser_head.system())
+            .add_system_to_stage("move", move_bear.system())
+            .add_system_to_stage("move", move_bird.system())
+            .add_system_to_stage("move", move_pushbox.system())
+            .add_system_to_stage("move", move_bullet.system())
+            .add_system_to_stage("move", move_blaster_head.system())
+            .add_system_to_stage("move", eyes_system.system())
+            .add_system_to_stage("move", force_field_system.system())
+            .add_system_to_stage("move_robbo", move_robbo.system())
+            .add_system_to_stage("shots", shot_system.system())
+            .add_system_to_stage("game_events", game_event_system.system())
+            .add_system_to_stage("game_events", reload_level_system.system())
+            .add_system_to_stage("game_events", game_event_use_item.system())
+            .add_system_to_stage("game_events", game_event_use_teleport.system())
+            .add_system_to_stage("count_game_events", count_game_events.system())
+            .add_system_to_stage("tick", activate_capsule_system.system())
+            .add_system_to_stage("tick", tick_system.system())
+            .add_system_to_stage("tick", damage_system.system());
+    }
+}
+
+/// Headless game: gameplay plugins only, driven by hand one keyframe per `step`.
+/// Input comes from the replay (which may have no frames at all).
+pub struct Simulation {
+    pub app: App,
+}
+
+impl Simulation {
+    pub fn new(level_set: LevelSet, replay: Replay) -> Simulation {
+        Simulation::with_plugins(level_set, replay, |_| ())
+    }
+
+    /// `add_plugins` may extend the headless app (e.g. with `RewindPlugin`)
+    pub fn with_plugins(
+        level_set: LevelSet,
+        replay: Replay,
+        add_plugins: impl FnOnce(&mut AppBuilder),
+    ) -> Simulation {
+        Simulation::with_key_frame_interval(1, level_set, replay, add_plugins)
+    }
+
+    /// like `with_plugins`, but only every `key_frame_interval`-th `step` is a keyframe
+    /// (for input arriving between keyframes)
+    pub fn with_key_frame_interval(
+        key_frame_interval: usize,
+        level_set: LevelSet,
+        replay: Replay,
+        add_plugins: impl FnOnce(&mut AppBuilder),
+    ) -> Simulation {
+        let mut builder = App::build();
+        builder
+            .add_plugin(bevy::reflect::ReflectPlugin)
+            .add_plugin(bevy::core::CorePlugin)
+            .add_plugin(bevy::asset::AssetPlugin)
+            .add_plugin(SimulationPlugin::new(key_frame_interval, replay.seed))
+            .add_system_to_stage("reload_level", reload_level.system());
+        add_plugins(&mut builder);
+
+        let level = replay.level;
+        builder.add_plugin(ReplayPlugin::Play(replay));
+        let resources = builder.resources_mut();
+        let handle = resources
+            .get_mut::<Assets<LevelSet>>()
+            .unwrap()
+            .add(level_set);
+        {
+            let mut level_info = resources.get_mut::<LevelInfo>().unwrap();
+            level_info.level_set_handle = handle;
+            level_info.current_level = level.max(1) - 1;
+        }
+        resources
+            .get_mut::<Events<GameEvent>>()
+            .unwrap()
+            .send(GameEvent::ReloadLevel(0));
+
+        Simulation {
+            app: std::mem::take(&mut builder.app),
+        }
+    }
+
+    /// advance the board by one keyframe (one frame with a longer keyframe interval)
+    pub fn step(&mut self) {
+        self.app.update();
+    }
+}
@@ -0,0 +1,236 @@
+use crate::consts::*;
+use crate::plugins::gamepad::Dirs;
+use crate::plugins::render::{ScreenFixed, Viewport};
+use bevy::input::touch::TouchInput;
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+
+/// height of the strip with touch controls below the status bar
+pub const CONTROLS_HEIGHT: f32 = 5.0 * 32.0;
+
+const BUTTON_Z: f32 = 20.0;
+const DPAD_CELL: f32 = 48.0;
+/// touches closer to the D-pad center don't press any direction
+const DPAD_DEAD_ZONE: f32 = 16.0;
+const FIRE_SIZE: f32 = 128.0;
+const PAUSE_SIZE: f32 = 64.0;
+
+/// On-screen D-pad, pause button and fire toggle for touch screens.
+/// Buttons are drawn in the strip of `CONTROLS_HEIGHT` below the status bar,
+/// taps on the board confirm (start the game, continue).
+#[derive(Default, Debug)]
+pub struct TouchControls {
+    /// controls are shown and the camera leaves room for them
+    pub enabled: bool,
+    pub pressed: Dirs,
+    pub just_pressed: Dirs,
+    /// directions shoot instead of moving while on (like held shift)
+    pub fire: bool,
+    pub pause: bool,
+    pub confirm: bool,
+}
+
+impl TouchControls {
+    /// enabled if asked for, always on devices with a touch screen (web only)
+    pub fn new(enabled: bool) -> TouchControls {
+        #[cfg(target_arch = "wasm32")]
+        let enabled = enabled
+            || web_sys::window()
+                .map(|window| window.navigator().max_touch_points() > 0)
+                .unwrap_or(false);
+        TouchControls {
+            enabled,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TouchButton {
+    Dir(i32, i32),
+    Pause,
+    Fire,
+}
+
+impl TouchButton {
+    const ALL: &'static [TouchButton] = &[
+        TouchButton::Dir(0, 1),
+        TouchButton::Dir(0, -1),
+        TouchButton::Dir(-1, 0),
+        TouchButton::Dir(1, 0),
+        TouchButton::Pause,
+        TouchButton::Fire,
+    ];
+
+    /// center and size in world coordinates (board tiles are 32 units)
+    fn rect(&self) -> (Vec2, f32) {
+        let center_y = -16.0 - CONTROLS_HEIGHT / 2.0;
+        let right = MAX_BOARD_WIDTH as f32 * 32.0 - 16.0;
+        match *self {
+            TouchButton::Dir(kx, ky) => (
+                dpad_center() + Vec2::new(kx as f32, ky as f32) * DPAD_CELL,
+                DPAD_CELL - 4.0,
+            ),
+            TouchButton::Pause => (Vec2::new(right / 2.0, center_y), PAUSE_SIZE),
+            TouchButton::Fire => (
+                Vec2::new(right - CONTROLS_HEIGHT / 2.0, center_y),
+                FIRE_SIZE,
+            ),
+        }
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        let (center, size) = self.rect();
+        let d = point - center;
+        d.x.abs() <= size / 2.0 && d.y.abs() <= size / 2.0
+    }
+
+    fn color(&self, is_on: bool) -> Color {
+        let alpha = if is_on { 0.7 } else { 0.25 };
+        match self {
+            TouchButton::Fire => Color::rgba(0.9, 0.3, 0.2, alpha),
+            _ => Color::rgba(0.9, 0.9, 0.9, alpha),
+        }
+    }
+}
+
+fn dpad_center() -> Vec2 {
+    Vec2::new(-16.0 + CONTROLS_HEIGHT / 2.0, -16.0 - CONTROLS_HEIGHT / 2.0)
+}
+
+/// direction pointed at on the D-pad (anywhere in its 3x3 cells square)
+fn dpad_dirs(point: Vec2) -> Dirs {
+    let d = point - dpad_center();
+    let is_inside = d.x.abs() <= 1.5 * DPAD_CELL && d.y.abs() <= 1.5 * DPAD_CELL;
+    if !is_inside || d.x.abs().max(d.y.abs()) < DPAD_DEAD_ZONE {
+        return Dirs::default();
+    }
+    let is_horizontal = d.x.abs() > d.y.abs();
+    Dirs {
+        right: is_horizontal && d.x > 0.0,
+        left: is_horizontal && d.x < 0.0,
+        up: !is_horizontal && d.y > 0.0,
+        down: !is_horizontal && d.y < 0.0,
+    }
+}
+
+/// Reads touches (and left mouse button, browsers emulate it for taps).
+/// Runs every frame, like `gamepad_system`.
+pub fn touch_system(
+    mut events: Local<EventReader<TouchInput>>,
+    touch_events: Res<Events<TouchInput>>,
+    (touches, mouse_buttons, windows): (Res<Touches>, Res<Input<MouseButton>>, Res<Windows>),
+    cameras: Query<(&Transform, &Viewport), With<Camera>>,
+    mut controls: ResMut<TouchControls>,
+) {
+    if events.iter(&touch_events).next().is_some() && !controls.enabled {
+        info!("touch screen used, showing touch controls");
+        controls.enabled = true;
+    }
+    controls.pause = false;
+    controls.confirm = false;
+    let (window, (camera, viewport)) = match (windows.get_primary(), cameras.iter().next()) {
+        (Some(window), Some(camera)) if controls.enabled => (window, camera),
+        _ => return,
+    };
+    // touch positions are from the top of the window (bottom on android), cursor from the bottom
+    let from_touch = |position: Vec2| {
+        if cfg!(target_os = "android") {
+            position
+        } else {
+            Vec2::new(position.x, window.height() - position.y)
+        }
+    };
+    // buttons don't scroll with the board
+    let to_world = |position: Vec2| {
+        camera.translation.truncate() - viewport.offset + position * camera.scale.x
+    };
+    let cursor = window
+        .cursor_position()
+        .filter(|_| mouse_buttons.pressed(MouseButton::Left));
+    let is_mouse_just_pressed = mouse_buttons.just_pressed(MouseButton::Left);
+
+    let held = touches
+        .iter()
+        .map(|touch| from_touch(touch.position()))
+        .chain(cursor)
+        .map(to_world);
+    let pressed = held.fold(Dirs::default(), |dirs, point| dirs.or(dpad_dirs(point)));
+    controls.just_pressed = pressed.since(controls.pressed);
+    controls.pressed = pressed;
+
+    let taps = touches
+        .iter_just_pressed()
+        .map(|touch| from_touch(touch.position()))
+        .chain(cursor.filter(|_| is_mouse_just_pressed))
+        .map(to_world);
+    for point in taps {
+        if TouchButton::Fire.contains(point) {
+            controls.fire = !controls.fire;
+        } else if TouchButton::Pause.contains(point) {
+            controls.pause = true;
+        } else if point.y > -16.0 {
+            controls.confirm = true;
+        }
+    }
+}
+
+/// Spawns the buttons once controls are enabled, highlights the pressed ones
+pub fn update_touch_buttons(
+    commands: &mut Commands,
+    mut is_spawned: Local<bool>,
+    controls: Res<TouchControls>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    buttons: Query<(&TouchButton, &Handle<ColorMaterial>)>,
+) {
+    if !controls.enabled {
+        return;
+    }
+    if !*is_spawned {
+        *is_spawned = true;
+        for button in TouchButton::ALL {
+            let (center, size) = button.rect();
+            let translation = center.extend(BUTTON_Z);
+            commands
+                .spawn(SpriteBundle {
+                    material: materials.add(button.color(false).into()),
+                    sprite: Sprite::new(Vec2::new(size, size)),
+                    transform: Transform::from_translation(translation),
+                    ..Default::default()
+                })
+                .with_bundle((*button, ScreenFixed(translation)));
+        }
+        // pause sign
+        let (center, _) = TouchButton::Pause.rect();
+        for dx in &[-10.0, 10.0] {
+            let translation = (center + Vec2::new(*dx, 0.0)).extend(BUTTON_Z + 1.0);
+            commands
+                .spawn(SpriteBundle {
+                    material: materials.add(Color::rgba(0.2, 0.2, 0.2, 0.8).into()),
+                    sprite: Sprite::new(Vec2::new(10.0, PAUSE_SIZE / 2.0)),
+                    transform: Transform::from_translation(translation),
+                    ..Default::default()
+                })
+                .with(ScreenFixed(translation));
+        }
+        return;
+    }
+    for (button, material) in buttons.iter() {
+        let is_on = match *button {
+            TouchButton::Dir(kx, ky) => {
+                let dirs = controls.pressed;
+                (
+                    dirs.right as i32 - dirs.left as i32,
+                    dirs.up as i32 - dirs.down as i32,
+                ) == (kx, ky)
+            }
+            TouchButton::Pause => false,
+            TouchButton::Fire => controls.fire,
+        };
+        let color = button.color(is_on);
+        // `get_mut` marks the material as modified, only call it on change
+        if matches!(materials.get(material), Some(current) if current.color != color) {
+            materials.get_mut(material).unwrap().color = color;
+        }
+    }
+}
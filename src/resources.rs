@@ -1,7 +1,9 @@
-use crate::components::Position;
+use crate::components::{Int2Ops, Position};
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::collections::HashMap;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct DamageMap(pub HashMap<Position, bool>);
 
 impl DamageMap {
@@ -14,4 +16,61 @@ impl DamageMap {
     pub fn is_damaged(&self, pos: &Position) -> bool {
         return self.0.contains_key(pos);
     }
+    /// damaged positions in stable (x, y) order, independent of hasher state
+    pub fn sorted(damage: &HashMap<Position, bool>) -> Vec<(Position, bool)> {
+        let mut damage: Vec<_> = damage.iter().map(|(pos, v)| (*pos, *v)).collect();
+        damage.sort_by_key(|(pos, _)| pos.as_tuple());
+        damage
+    }
+}
+
+/// Number of game events in the current buffer already handled at the last keyframe;
+/// the rest is waiting for the next keyframe (and has to be kept in board snapshots)
+#[derive(Default)]
+pub struct ProcessedGameEvents(pub usize);
+
+/// Seeded source of randomness for all gameplay systems.
+/// Systems drawing from it live in distinct stages (move -> shots -> game_events -> tick),
+/// so the same seed and the same input always give the same board.
+/// Systems moving or animating several entities visit them sorted by position: query order
+/// follows entity ids, which are reused after earlier levels are despawned, so it isn't the same
+/// for a level played after others and for the same level started fresh (e.g. from a replay).
+#[derive(Clone)]
+pub struct GameRng {
+    seed: u64,
+    stream: u64,
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self::from_state(seed, 0, 0)
+    }
+    /// continues the sequence of `state()`
+    pub fn from_state(seed: u64, stream: u64, word_pos: u128) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(stream);
+        rng.set_word_pos(word_pos);
+        Self { seed, stream, rng }
+    }
+    /// seed, stream and position in the stream
+    pub fn state(&self) -> (u64, u64, u128) {
+        (self.seed, self.stream, self.rng.get_word_pos())
+    }
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// start level's own random stream, so every attempt of a level
+    /// depends only on the seed and the player's input
+    pub fn restart(&mut self, level: usize) {
+        *self = Self::from_state(self.seed, level as u64, 0);
+    }
+    /// true with probability `p`
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.rng.gen::<f32>() < p
+    }
+    /// uniform-ish index in `0..n`, same sequence on 32 and 64 bit targets
+    pub fn index(&mut self, n: usize) -> usize {
+        self.rng.next_u32() as usize % n
+    }
 }
@@ -0,0 +1,621 @@
+use crate::components::prelude::*;
+use crate::game_events::GameEvent;
+use crate::inventory::Inventory;
+use crate::levels::LevelInfo;
+use crate::plugins::keyboard::InputQueue;
+use crate::plugins::FrameCnt;
+use crate::resources::{DamageMap, GameRng, ProcessedGameEvents};
+use anyhow::{anyhow, Context};
+use bevy::ecs::EntityBuilder;
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// version of saved snapshots, bump on incompatible changes of the format
+pub const SAVE_VERSION: u32 = 2;
+
+/// Text form of a component in saved snapshots
+pub trait SaveValue: Sized {
+    fn save(&self) -> String;
+    fn load(value: &str) -> anyhow::Result<Self>;
+}
+
+macro_rules! unit_save_value {
+    ($($component:ident),* $(,)?) => {
+        $(impl SaveValue for $component {
+            fn save(&self) -> String {
+                String::new()
+            }
+            fn load(_value: &str) -> anyhow::Result<Self> {
+                Ok($component)
+            }
+        })*
+    };
+}
+
+unit_save_value!(
+    Robbo,
+    Bomb2,
+    Bird,
+    MovingBetweenWalls,
+    Eyes,
+    Bullet,
+    Wall,
+    PushBox,
+    QuestionMark,
+    Stop,
+    RadioactiveField,
+    Moveable,
+    Destroyable,
+    Undestroyable,
+    Deadly,
+    Capsule,
+    LaserTail,
+    BlasterHead,
+    RoughUpdate,
+);
+
+macro_rules! int2_save_value {
+    ($($component:ident),* $(,)?) => {
+        $(impl SaveValue for $component {
+            fn save(&self) -> String {
+                format!("{}.{}", self.x(), self.y())
+            }
+            fn load(value: &str) -> anyhow::Result<Self> {
+                let (x, y) = parse_pair(value)?;
+                Ok($component::new(x, y))
+            }
+        })*
+    };
+}
+
+int2_save_value!(Position, MovingDir, ShootingDir, Magnet);
+
+/// enums without data are saved by variant name
+macro_rules! enum_save_value {
+    ($component:ident { $($variant:ident: $name:literal),* $(,)? }) => {
+        impl SaveValue for $component {
+            fn save(&self) -> String {
+                match self {
+                    $($component::$variant => $name,)*
+                }
+                .to_string()
+            }
+            fn load(value: &str) -> anyhow::Result<Self> {
+                match value {
+                    $($name => Ok($component::$variant),)*
+                    _ => Err(anyhow!("invalid {} {:?}", stringify!($component), value)),
+                }
+            }
+        }
+    };
+}
+
+enum_save_value!(Usable {
+    Door: "door",
+    Teleport: "teleport",
+    Capsule: "capsule",
+});
+enum_save_value!(Collectable {
+    Key: "key",
+    Screw: "screw",
+    Ammo: "ammo",
+    Life: "life",
+});
+enum_save_value!(Gun {
+    Solid: "solid",
+    Blaster: "blaster",
+    Burst: "burst",
+});
+enum_save_value!(Rotatable {
+    Regular: "regular",
+    Random: "random",
+});
+
+/// "a.b"
+fn parse_pair<T>(value: &str) -> anyhow::Result<(T, T)>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let mut it = value.split('.').map(|v| v.parse::<T>());
+    match (it.next(), it.next(), it.next()) {
+        (Some(a), Some(b), None) => Ok((a?, b?)),
+        _ => Err(anyhow!("invalid pair {:?}", value)),
+    }
+}
+
+fn parse_flag(value: &str) -> anyhow::Result<bool> {
+    match value {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(anyhow!("invalid flag {:?}", value)),
+    }
+}
+
+/// "-" for `None`
+fn save_option<T: ToString>(value: Option<T>) -> String {
+    value.map_or_else(|| "-".to_string(), |value| value.to_string())
+}
+
+fn parse_option<T>(value: &str) -> anyhow::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match value {
+        "-" => Ok(None),
+        value => Ok(Some(value.parse()?)),
+    }
+}
+
+/// `Use` refers to an entity, it is never pending between keyframes
+fn save_event(event: &GameEvent) -> Option<String> {
+    Some(match event {
+        GameEvent::Use(..) => return None,
+        GameEvent::ReloadLevel(k) => format!("reload_level/{}", k),
+        GameEvent::SpawnRobbo(pos) => format!("spawn_robbo/{}", pos.save()),
+        GameEvent::PreSpawnRobbo(pos) => format!("pre_spawn_robbo/{}", pos.save()),
+        GameEvent::SpawnRandom(pos) => format!("spawn_random/{}", pos.save()),
+        GameEvent::ShootAround(pos) => format!("shoot_around/{}", pos.save()),
+        GameEvent::LevelComplete => "level_complete".to_string(),
+        GameEvent::KillRobbo => "kill_robbo".to_string(),
+    })
+}
+
+fn load_event(value: &str) -> anyhow::Result<GameEvent> {
+    let (name, arg) = value.split_once('/').unwrap_or((value, ""));
+    Ok(match name {
+        "reload_level" => GameEvent::ReloadLevel(arg.parse()?),
+        "spawn_robbo" => GameEvent::SpawnRobbo(Position::load(arg)?),
+        "pre_spawn_robbo" => GameEvent::PreSpawnRobbo(Position::load(arg)?),
+        "spawn_random" => GameEvent::SpawnRandom(Position::load(arg)?),
+        "shoot_around" => GameEvent::ShootAround(Position::load(arg)?),
+        "level_complete" => GameEvent::LevelComplete,
+        "kill_robbo" => GameEvent::KillRobbo,
+        _ => return Err(anyhow!("unknown event {:?}", value)),
+    })
+}
+
+impl SaveValue for Bomb {
+    fn save(&self) -> String {
+        (self.0 as u8).to_string()
+    }
+    fn load(value: &str) -> anyhow::Result<Self> {
+        Ok(Bomb(parse_flag(value)?))
+    }
+}
+
+impl SaveValue for Bear {
+    fn save(&self) -> String {
+        (self.0 as u8).to_string()
+    }
+    fn load(value: &str) -> anyhow::Result<Self> {
+        Ok(Bear(parse_flag(value)?))
+    }
+}
+
+impl SaveValue for Tiles {
+    fn save(&self) -> String {
+        let tiles: Vec<_> = self.tiles().iter().map(|tile| tile.to_string()).collect();
+        format!("{}/{}", tiles.join(","), self.current)
+    }
+    fn load(value: &str) -> anyhow::Result<Self> {
+        let (tiles, current) = value
+            .split_once('/')
+            .ok_or_else(|| anyhow!("invalid tiles {:?}", value))?;
+        let tiles = tiles
+            .split(',')
+            .map(|tile| tile.parse())
+            .collect::<Result<Vec<u32>, _>>()?;
+        let current = current.parse()?;
+        if current >= tiles.len() || tiles.len() > MAX_TILES {
+            return Err(anyhow!("invalid tiles {:?}", value));
+        }
+        let mut loaded = Tiles::new(&tiles);
+        loaded.current = current;
+        Ok(loaded)
+    }
+}
+
+impl SaveValue for Animation {
+    fn save(&self) -> String {
+        self.0.as_ref().and_then(save_event).unwrap_or_default()
+    }
+    fn load(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "" => Ok(Animation(None)),
+            value => Ok(Animation(Some(load_event(value)?))),
+        }
+    }
+}
+
+impl SaveValue for ForceField {
+    fn save(&self) -> String {
+        self.0.save()
+    }
+    fn load(value: &str) -> anyhow::Result<Self> {
+        Ok(ForceField(MovingDir::load(value)?))
+    }
+}
+
+impl SaveValue for ForceFieldBounds {
+    fn save(&self) -> String {
+        format!("{}.{}", self.0, self.1)
+    }
+    fn load(value: &str) -> anyhow::Result<Self> {
+        let (a, b) = parse_pair(value)?;
+        Ok(ForceFieldBounds(a, b))
+    }
+}
+
+impl SaveValue for Teleport {
+    fn save(&self) -> String {
+        format!("{}.{}", self.0, self.1)
+    }
+    fn load(value: &str) -> anyhow::Result<Self> {
+        let (a, b) = parse_pair(value)?;
+        Ok(Teleport(a, b))
+    }
+}
+
+impl SaveValue for LaserHead {
+    fn save(&self) -> String {
+        format!("{}/{}", self.gun_pos.save(), self.is_moving_back as u8)
+    }
+    fn load(value: &str) -> anyhow::Result<Self> {
+        let (gun_pos, is_moving_back) = value
+            .split_once('/')
+            .ok_or_else(|| anyhow!("invalid laser head {:?}", value))?;
+        Ok(LaserHead {
+            gun_pos: Position::load(gun_pos)?,
+            is_moving_back: parse_flag(is_moving_back)?,
+        })
+    }
+}
+
+impl SaveValue for ShootingProp {
+    fn save(&self) -> String {
+        self.0.to_string()
+    }
+    fn load(value: &str) -> anyhow::Result<Self> {
+        Ok(ShootingProp(value.parse()?))
+    }
+}
+
+macro_rules! entity_snapshot {
+    ($($field:ident: $component:ty),* $(,)?) => {
+        /// Gameplay components of a single board entity (sprites are recreated by `create_sprites`)
+        #[derive(Clone, Default)]
+        pub struct EntitySnapshot {
+            $($field: Option<$component>,)*
+        }
+
+        impl EntitySnapshot {
+            pub fn capture(world: &World, entity: Entity) -> Self {
+                Self {
+                    $($field: world.get::<$component>(entity).ok().copied(),)*
+                }
+            }
+            pub fn spawn(&self, world: &mut World) -> Entity {
+                let mut builder = EntityBuilder::new();
+                $(if let Some(component) = self.$field {
+                    builder.add(component);
+                })*
+                world.spawn(builder.build())
+            }
+            /// space separated `name` or `name:value` tokens
+            pub fn save(&self) -> String {
+                let mut tokens = vec![];
+                $(if let Some(component) = self.$field.as_ref() {
+                    let value = component.save();
+                    tokens.push(if value.is_empty() {
+                        stringify!($field).to_string()
+                    } else {
+                        format!("{}:{}", stringify!($field), value)
+                    });
+                })*
+                tokens.join(" ")
+            }
+            pub fn load(line: &str) -> anyhow::Result<Self> {
+                let mut entity = Self::default();
+                for token in line.split_whitespace() {
+                    let (name, value) = token.split_once(':').unwrap_or((token, ""));
+                    match name {
+                        $(stringify!($field) => {
+                            let component = SaveValue::load(value)
+                                .with_context(|| format!("invalid {}", name))?;
+                            entity.$field = Some(component);
+                        })*
+                        _ => return Err(anyhow!("unknown component {:?}", name)),
+                    }
+                }
+                if entity.position.is_none() {
+                    return Err(anyhow!("entity without position"));
+                }
+                Ok(entity)
+            }
+        }
+    };
+}
+
+entity_snapshot! {
+    position: Position,
+    moving_dir: MovingDir,
+    tiles: Tiles,
+    robbo: Robbo,
+    bomb: Bomb,
+    bomb2: Bomb2,
+    bird: Bird,
+    moving_between_walls: MovingBetweenWalls,
+    bear: Bear,
+    eyes: Eyes,
+    bullet: Bullet,
+    wall: Wall,
+    push_box: PushBox,
+    question_mark: QuestionMark,
+    stop: Stop,
+    radioactive_field: RadioactiveField,
+    animation: Animation,
+    force_field: ForceField,
+    force_field_bounds: ForceFieldBounds,
+    moveable: Moveable,
+    destroyable: Destroyable,
+    undestroyable: Undestroyable,
+    deadly: Deadly,
+    usable: Usable,
+    teleport: Teleport,
+    collectable: Collectable,
+    capsule: Capsule,
+    laser_head: LaserHead,
+    laser_tail: LaserTail,
+    blaster_head: BlasterHead,
+    rough_update: RoughUpdate,
+    gun: Gun,
+    shooting_dir: ShootingDir,
+    shooting_prop: ShootingProp,
+    rotatable: Rotatable,
+    magnet: Magnet,
+}
+
+fn board_entities(world: &World, with_walls: bool) -> Vec<Entity> {
+    world
+        .query_filtered::<Entity, With<Position>>()
+        .filter(|entity| with_walls || world.get::<Wall>(*entity).is_err())
+        .collect()
+}
+
+/// Whole board at the end of a frame: entities, gameplay resources
+/// and game events waiting to be processed at the next keyframe.
+/// Walls never change during level attempt, they are kept only in full snapshots
+/// (which may be saved and restored in another level attempt).
+#[derive(Clone)]
+pub struct Snapshot {
+    pub frame: usize,
+    with_walls: bool,
+    entities: Vec<EntitySnapshot>,
+    inventory: Inventory,
+    level_info: LevelInfo,
+    damage_map: DamageMap,
+    rng: GameRng,
+    input_queue: Option<InputQueue>,
+    game_events: Vec<GameEvent>,
+}
+
+impl Snapshot {
+    /// board without walls, for restoring in the same level attempt
+    pub fn capture(world: &World, resources: &Resources) -> Snapshot {
+        Snapshot::capture_entities(world, resources, false)
+    }
+
+    /// board with walls, restores any level attempt exactly
+    pub fn capture_full(world: &World, resources: &Resources) -> Snapshot {
+        Snapshot::capture_entities(world, resources, true)
+    }
+
+    fn capture_entities(world: &World, resources: &Resources, with_walls: bool) -> Snapshot {
+        let entities = board_entities(world, with_walls)
+            .into_iter()
+            .map(|entity| EntitySnapshot::capture(world, entity))
+            .collect();
+        let processed_events = resources.get::<ProcessedGameEvents>().unwrap().0;
+        let game_events = resources.get::<Events<GameEvent>>().unwrap();
+        Snapshot {
+            frame: resources.get::<FrameCnt>().unwrap().value(),
+            with_walls,
+            entities,
+            inventory: (*resources.get::<Inventory>().unwrap()).clone(),
+            level_info: (*resources.get::<LevelInfo>().unwrap()).clone(),
+            damage_map: (*resources.get::<DamageMap>().unwrap()).clone(),
+            rng: (*resources.get::<GameRng>().unwrap()).clone(),
+            input_queue: resources
+                .get::<InputQueue>()
+                .map(|input_queue| (*input_queue).clone()),
+            game_events: game_events
+                .iter_current_update_events()
+                .skip(processed_events)
+                .copied()
+                .collect(),
+        }
+    }
+
+    pub fn level_start_frame(&self) -> Option<usize> {
+        self.level_info.start_frame
+    }
+
+    /// replaces the board with the snapshot, frame counter goes back too
+    pub fn restore(&self, world: &mut World, resources: &Resources) {
+        for entity in board_entities(world, self.with_walls) {
+            world.despawn(entity).unwrap();
+        }
+        for entity in &self.entities {
+            entity.spawn(world);
+        }
+        resources.get_mut::<FrameCnt>().unwrap().set(self.frame);
+        *resources.get_mut::<Inventory>().unwrap() = self.inventory.clone();
+        {
+            // loaded snapshots don't know the levelset handle
+            let mut level_info = resources.get_mut::<LevelInfo>().unwrap();
+            *level_info = LevelInfo {
+                level_set_handle: level_info.level_set_handle.clone(),
+                ..self.level_info.clone()
+            };
+        }
+        *resources.get_mut::<DamageMap>().unwrap() = self.damage_map.clone();
+        *resources.get_mut::<GameRng>().unwrap() = self.rng.clone();
+        if let Some(mut input_queue) = resources.get_mut::<InputQueue>() {
+            *input_queue = self.input_queue.clone().unwrap_or_default();
+        }
+        // readers only see events sent after they last read, so pending ones are sent again
+        let mut game_events = resources.get_mut::<Events<GameEvent>>().unwrap();
+        game_events.clear();
+        game_events.extend(self.game_events.iter().copied());
+        resources.get_mut::<ProcessedGameEvents>().unwrap().0 = 0;
+    }
+
+    /// reads full snapshot written with `Display`
+    pub fn parse(data: &str) -> anyhow::Result<Snapshot> {
+        let mut snapshot = Snapshot {
+            frame: 0,
+            with_walls: true,
+            entities: vec![],
+            inventory: Inventory::default(),
+            level_info: LevelInfo::default(),
+            damage_map: DamageMap::default(),
+            rng: GameRng::new(0),
+            input_queue: None,
+            game_events: vec![],
+        };
+        // "[section]key" -> value
+        let mut values: HashMap<String, &str> = HashMap::new();
+        let mut section = "";
+        for (n, line) in data.lines().enumerate() {
+            let line = line.trim();
+            let context = || format!("line {}: {:?}", n + 1, line);
+            if line.is_empty() {
+                continue;
+            } else if line.starts_with('[') {
+                section = line;
+                continue;
+            }
+            match section {
+                "[damage]" => {
+                    let (pos, is_bomb) = line.split_once(' ').unwrap_or((line, ""));
+                    let pos = Position::load(pos).with_context(context)?;
+                    let is_bomb = parse_flag(is_bomb).with_context(context)?;
+                    snapshot.damage_map.do_damage(&pos, is_bomb);
+                }
+                "[events]" => snapshot
+                    .game_events
+                    .push(load_event(line).with_context(context)?),
+                "[wall_positions]" => {
+                    let pos = Position::load(line).with_context(context)?;
+                    snapshot.level_info.wall_positions.insert(pos);
+                }
+                "[input_queue]" => snapshot
+                    .input_queue
+                    .get_or_insert_with(InputQueue::default)
+                    .queue
+                    .push_back(line.parse().with_context(context)?),
+                "[entities]" => snapshot
+                    .entities
+                    .push(EntitySnapshot::load(line).with_context(context)?),
+                "" => return Err(anyhow!("line {}: unexpected {:?}", n + 1, line)),
+                section => {
+                    let (key, value) = line.split_once(' ').unwrap_or(("", line));
+                    values.insert(format!("{}{}", section, key), value);
+                }
+            }
+        }
+        let value = |key: &str| {
+            values
+                .get(key)
+                .copied()
+                .ok_or_else(|| anyhow!("missing {}", key))
+        };
+        let version: u32 = value("[version]")?.parse()?;
+        if version != SAVE_VERSION {
+            return Err(anyhow!("unsupported version {}", version));
+        }
+        snapshot.frame = value("[frame]")?.parse()?;
+        snapshot.rng = GameRng::from_state(
+            value("[rng]seed")?.parse()?,
+            value("[rng]stream")?.parse()?,
+            value("[rng]word_pos")?.parse()?,
+        );
+        snapshot.inventory = Inventory {
+            keys: value("[inventory]keys")?.parse()?,
+            screws: value("[inventory]screws")?.parse()?,
+            bullets: value("[inventory]bullets")?.parse()?,
+            lives: parse_option(value("[inventory]lives")?)?,
+        };
+        let level_info = &mut snapshot.level_info;
+        level_info.current_level = value("[level_info]current_level")?.parse()?;
+        level_info.width = value("[level_info]width")?.parse()?;
+        level_info.height = value("[level_info]height")?.parse()?;
+        level_info.screws = value("[level_info]screws")?.parse()?;
+        level_info.missing_robbo_ticks = value("[level_info]missing_robbo_ticks")?.parse()?;
+        level_info.start_frame = parse_option(value("[level_info]start_frame")?)?;
+        Ok(snapshot)
+    }
+}
+
+/// Versioned text form of full snapshots (quicksave files)
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[version]\n{}\n[frame]\n{}", SAVE_VERSION, self.frame)?;
+        let (seed, stream, word_pos) = self.rng.state();
+        writeln!(
+            f,
+            "[rng]\nseed {}\nstream {}\nword_pos {}",
+            seed, stream, word_pos
+        )?;
+        let inventory = &self.inventory;
+        writeln!(
+            f,
+            "[inventory]\nkeys {}\nscrews {}\nbullets {}\nlives {}",
+            inventory.keys,
+            inventory.screws,
+            inventory.bullets,
+            save_option(inventory.lives)
+        )?;
+        let level_info = &self.level_info;
+        writeln!(
+            f,
+            "[level_info]\ncurrent_level {}\nwidth {}\nheight {}\nscrews {}",
+            level_info.current_level, level_info.width, level_info.height, level_info.screws,
+        )?;
+        writeln!(
+            f,
+            "missing_robbo_ticks {}\nstart_frame {}",
+            level_info.missing_robbo_ticks,
+            save_option(level_info.start_frame)
+        )?;
+        if let Some(input_queue) = self.input_queue.as_ref() {
+            writeln!(f, "[input_queue]")?;
+            for frame in &input_queue.queue {
+                writeln!(f, "{}", frame)?;
+            }
+        }
+        writeln!(f, "[damage]")?;
+        for (pos, is_bomb) in DamageMap::sorted(&self.damage_map.0) {
+            writeln!(f, "{} {}", pos.save(), is_bomb as u8)?;
+        }
+        writeln!(f, "[events]")?;
+        for event in self.game_events.iter().filter_map(save_event) {
+            writeln!(f, "{}", event)?;
+        }
+        writeln!(f, "[wall_positions]")?;
+        let mut wall_positions: Vec<_> = self.level_info.wall_positions.iter().collect();
+        wall_positions.sort_by_key(|pos| pos.as_tuple());
+        for pos in wall_positions {
+            writeln!(f, "{}", pos.save())?;
+        }
+        writeln!(f, "[entities]")?;
+        for entity in &self.entities {
+            writeln!(f, "{}", entity.save())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+#[cfg(target_arch = "wasm32")]
+use anyhow::anyhow;
+use std::fmt;
+use std::path::Path;
+
+/// Place for data saved between games (progress and quicksave of a levelset, controls):
+/// a file in save dir on native, localStorage item on web
+#[derive(Debug, Clone)]
+pub struct Storage {
+    #[cfg(not(target_arch = "wasm32"))]
+    path: std::path::PathBuf,
+    #[cfg(target_arch = "wasm32")]
+    key: String,
+}
+
+impl Storage {
+    /// `<levelset>.<kind>`
+    pub fn new(save_dir: &Path, levelset_path: &Path, kind: &str) -> Storage {
+        let name = levelset_path
+            .file_stem()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Storage::named(save_dir, &format!("{}.{}", name, kind))
+    }
+
+    pub fn named(save_dir: &Path, name: &str) -> Storage {
+        #[cfg(target_arch = "wasm32")]
+        let _ = save_dir;
+        Storage {
+            #[cfg(not(target_arch = "wasm32"))]
+            path: save_dir.join(name),
+            #[cfg(target_arch = "wasm32")]
+            key: format!("robbo-{}", name),
+        }
+    }
+
+    /// `None` if nothing was saved yet
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read(&self) -> anyhow::Result<Option<String>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write(&self, data: &str) -> anyhow::Result<()> {
+        Ok(std::fs::write(&self.path, data)?)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn local_storage() -> anyhow::Result<web_sys::Storage> {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .ok_or_else(|| anyhow!("localStorage is not available"))
+    }
+
+    /// `None` if nothing was saved yet
+    #[cfg(target_arch = "wasm32")]
+    pub fn read(&self) -> anyhow::Result<Option<String>> {
+        Self::local_storage()?
+            .get_item(&self.key)
+            .map_err(|err| anyhow!("{:?}", err))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn write(&self, data: &str) -> anyhow::Result<()> {
+        Self::local_storage()?
+            .set_item(&self.key, data)
+            .map_err(|err| anyhow!("{:?}", err))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl fmt::Display for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.path)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl fmt::Display for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "localStorage {:?}", self.key)
+    }
+}
@@ -17,7 +17,9 @@ pub fn move_bear(
         return;
     }
     let mut occupied = level_info.get_occupied(queries.q1());
-    for (bear, mut position, mut dir) in queries.q0_mut().iter_mut() {
+    let mut bears = queries.q0_mut().iter_mut().collect::<Vec<_>>();
+    bears.sort_by_cached_key(|(_, pos, _)| pos.as_tuple());
+    for (bear, mut position, mut dir) in bears {
         if damage_map.is_damaged(&*position) {
             continue;
         }
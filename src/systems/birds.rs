@@ -22,7 +22,9 @@ pub fn move_bird(
         return;
     }
     let mut occupied = level_info.get_occupied(queries.q0());
-    for (mut position, mut dir) in queries.q1_mut().iter_mut() {
+    let mut birds = queries.q1_mut().iter_mut().collect::<Vec<_>>();
+    birds.sort_by_cached_key(|(pos, _)| pos.as_tuple());
+    for (mut position, mut dir) in birds {
         if damage_map.is_damaged(&*position) {
             continue;
         }
@@ -19,7 +19,9 @@ pub fn move_blaster_head(
     }
     let mut occupied = level_info.get_occupied(queries.q0());
 
-    for (entity, mut position, dir) in queries.q1_mut().iter_mut() {
+    let mut blaster_heads = queries.q1_mut().iter_mut().collect::<Vec<_>>();
+    blaster_heads.sort_by_cached_key(|(_, pos, _)| pos.as_tuple());
+    for (entity, mut position, dir) in blaster_heads {
         let new_pos = position.add(&*dir);
         let old_pos = *position;
         let is_wall = level_info.is_occupied(&new_pos);
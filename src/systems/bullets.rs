@@ -19,7 +19,9 @@ pub fn move_bullet(
         return;
     }
     let mut occupied = level_info.get_occupied( queries.q1());
-    for (entity, mut position, mut dir) in queries.q0_mut().iter_mut() {
+    let mut bullets = queries.q0_mut().iter_mut().collect::<Vec<_>>();
+    bullets.sort_by_cached_key(|(_, pos, _)| pos.as_tuple());
+    for (entity, mut position, mut dir) in bullets {
         let new_pos = position.add(&*dir);
         if occupied.is_occupied(&new_pos) {
             *dir = MovingDir::zero();
@@ -1,5 +1,5 @@
 use crate::components::prelude::*;
-use crate::entities::{create_explosion, spawn_random};
+use crate::entities::{create_bomb2_explosion, create_explosion, spawn_random};
 use crate::frame_cnt::FrameCnt;
 use crate::resources::DamageMap;
 use crate::plugins::audio::Sound;
@@ -42,13 +42,16 @@ pub fn damage_system(
 
 pub fn process_damage(
     commands: &mut Commands,
-    frame_cnt: Res<FrameCnt>,
-    level_info: Res<LevelInfo>,
-    mut sounds: ResMut<Events<Sound>>,
-    mut damage_map: ResMut<DamageMap>,
+    (frame_cnt, level_info, mut sounds, mut damage_map): (
+        Res<FrameCnt>,
+        Res<LevelInfo>,
+        ResMut<Events<Sound>>,
+        ResMut<DamageMap>,
+    ),
     items: Query<(Entity, &Position), Without<Undestroyable>>,
     mut bombs: Query<&mut Bomb>,
     destroyable: Query<&Destroyable>,
+    bombs2: Query<&Bomb2>,
 ) {
     if !frame_cnt.is_keyframe() {
         return;
@@ -85,19 +88,24 @@ pub fn process_damage(
             };
             if destroyable.get_component::<Destroyable>(entity).is_ok() || *is_bomb_damage {
                 commands.despawn(entity);
+                let is_bomb2 = bombs2.get(entity).is_ok();
 
                 if destroyable.get_component::<QuestionMark>(entity).is_ok() {
                     spawn_random(commands, *pos).with(*pos);
+                } else if is_bomb2 {
+                    create_bomb2_explosion(commands, *pos).with(*pos);
                 } else {
                     create_explosion(commands).with(*pos);
                 }
-                if !is_bomb_entity && !is_bomb_damage {
+                if is_bomb2 {
+                    sounds.send(Sound::BOMB);
+                } else if !is_bomb_entity && !is_bomb_damage {
                     sounds.send(Sound::BURN);
                 }
             }
         }
     }
-    for (&pos, &is_bomb_damage) in damage.iter() {
+    for (pos, is_bomb_damage) in DamageMap::sorted(&damage) {
         if is_bomb_damage && !damaged_entities.contains(&pos) && !level_info.is_occupied(&pos) {
             create_explosion(commands).with(pos);
         }
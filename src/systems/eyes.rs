@@ -1,8 +1,8 @@
 use crate::components::prelude::*;
 use crate::frame_cnt::FrameCnt;
 use crate::levels::LevelInfo;
+use crate::resources::GameRng;
 use bevy::prelude::*;
-use rand::random;
 use std::collections::HashSet;
 
 const RANDOM_MOVE_PROP: f32 = 0.5;
@@ -10,6 +10,7 @@ const RANDOM_MOVE_PROP: f32 = 0.5;
 pub fn eyes_system(
     frame_cnt: Res<FrameCnt>,
     level_info: Res<LevelInfo>,
+    mut rng: ResMut<GameRng>,
     mut queries: QuerySet<(
        Query<&Position, Without<Wall>>,
        Query<&Position, With<Robbo>>,
@@ -21,9 +22,11 @@ pub fn eyes_system(
     }
     let occupied: HashSet<Position> = queries.q0().iter().cloned().collect();
     if let Some(robbo_pos) = queries.q1().iter().cloned().next() {
-        for mut eyes_pos in queries.q2_mut().iter_mut() {
-            let (dx, dy) = if random::<f32>() < RANDOM_MOVE_PROP {
-                MovingDir::by_index(random::<usize>() % 4).as_tuple()
+        let mut eyes = queries.q2_mut().iter_mut().collect::<Vec<_>>();
+        eyes.sort_by_cached_key(|pos| pos.as_tuple());
+        for mut eyes_pos in eyes {
+            let (dx, dy) = if rng.chance(RANDOM_MOVE_PROP) {
+                MovingDir::by_index(rng.index(4)).as_tuple()
             } else {
                 robbo_pos.add(&eyes_pos.neg()).as_tuple()
             };
@@ -1,13 +1,13 @@
-use crate::components::{Position, Robbo, Teleport, Usable};
+use crate::components::{Int2Ops, MovingDir, Position, Robbo, Teleport, Usable, Wall};
 use crate::entities::*;
 use crate::frame_cnt::FrameCnt;
 use crate::game_events::GameEvent;
 use crate::inventory::Inventory;
 use crate::levels::{create_level, LevelInfo, LevelSet};
 use crate::plugins::audio::Sound;
-use crate::resources::DamageMap;
+use crate::plugins::GameState;
+use crate::resources::{DamageMap, GameRng, ProcessedGameEvents};
 use crate::systems::utils::teleport_dest_position;
-use rand::random;
 
 use bevy::prelude::*;
 use std::collections::HashSet;
@@ -23,6 +23,16 @@ pub fn update_game_events(frame_cnt: Res<FrameCnt>, events: ResMut<Events<GameEv
     }
 }
 
+pub fn count_game_events(
+    frame_cnt: Res<FrameCnt>,
+    events: Res<Events<GameEvent>>,
+    mut processed: ResMut<ProcessedGameEvents>,
+) {
+    if frame_cnt.is_keyframe() {
+        processed.0 = events.iter_current_update_events().count();
+    }
+}
+
 #[derive(Default)]
 pub struct ReloadLevelState {
     pub events: EventReader<GameEvent>,
@@ -31,11 +41,14 @@ pub struct ReloadLevelState {
 pub fn reload_level_system(
     commands: &mut Commands,
     mut state: Local<ReloadLevelState>,
-    game_events: ResMut<Events<GameEvent>>,
-    frame_cnt: Res<FrameCnt>,
-    level_sets: Res<Assets<LevelSet>>,
-    mut level_info: ResMut<LevelInfo>,
-    mut inventory: ResMut<Inventory>,
+    (game_events, frame_cnt, level_sets, mut level_info, mut inventory, mut rng): (
+        ResMut<Events<GameEvent>>,
+        Res<FrameCnt>,
+        Res<Assets<LevelSet>>,
+        ResMut<LevelInfo>,
+        ResMut<Inventory>,
+        ResMut<GameRng>,
+    ),
     mut all_positions: Query<(Entity, &Position)>,
 ) {
     if !frame_cnt.is_keyframe() {
@@ -47,11 +60,16 @@ pub fn reload_level_system(
             if let Some(level_set) = level_sets.get(&level_info.level_set_handle) {
                 let level = level_info.inc_current_level(k, level_set);
                 level_info.missing_robbo_ticks = 0;
+                level_info.start_frame = Some(frame_cnt.value());
                 level_info.screws = level.screw_count;
                 level_info.width = level.height;
                 level_info.height = level.width;
                 create_level(commands, &mut all_positions, level, &mut level_info);
-                *inventory = Inventory::default();
+                *inventory = Inventory {
+                    lives: inventory.lives,
+                    ..Default::default()
+                };
+                rng.restart(level_info.current_level);
                 return;
             }
         }
@@ -63,22 +81,30 @@ pub fn game_event_system(
     mut state: Local<State>,
     (
         frame_cnt,
-        game_events,
+        mut game_events,
         mut damage_map,
         mut sounds,
+        mut rng,
+        level_info,
+        mut game_state,
     ): (
         Res<FrameCnt>,
         ResMut<Events<GameEvent>>,
         ResMut<DamageMap>,
         ResMut<Events<Sound>>,
+        ResMut<GameRng>,
+        Res<LevelInfo>,
+        ResMut<bevy::ecs::State<GameState>>,
     ),
-    mut robbo: Query<(Entity, &mut Position), With<Robbo>>,
+    robbo: Query<&Position, With<Robbo>>,
+    items: Query<(&Position, Entity), Without<Wall>>,
 ) {
     if !frame_cnt.is_keyframe() {
         return;
     }
     // let mut despawned = HashSet::new();
 
+    let mut next_level = false;
     for event in state.events.iter(&game_events) {
         info!("game_event: {:?}", event);
         match *event {
@@ -103,16 +129,39 @@ pub fn game_event_system(
                     create_questionmark_gun,
                     create_questionmark,
                 ];
-                create_item[random::<usize>() % create_item.len()](commands).with(pos);
+                create_item[rng.index(create_item.len())](commands).with(pos);
             }
             GameEvent::KillRobbo => {
-                for (_, pos) in robbo.iter_mut() {
-                    damage_map.do_damage(&*pos, false);
+                for pos in robbo.iter() {
+                    damage_map.do_damage(pos, false);
                 }
             }
+            GameEvent::ShootAround(pos) => {
+                let mut occupied = level_info.get_occupied(&items);
+                for index in 0..4 {
+                    let dir = MovingDir::by_index(index);
+                    let bullet_pos = pos.add(&dir);
+                    if occupied.is_free(&bullet_pos) {
+                        create_bullet(commands, dir.x(), dir.y()).with(bullet_pos);
+                        occupied.put_entity(&bullet_pos, commands.current_entity().unwrap());
+                    } else {
+                        damage_map.do_damage(&bullet_pos, false);
+                    }
+                }
+                sounds.send(Sound::SHOT);
+            }
+            GameEvent::LevelComplete => {
+                next_level = true;
+                game_state.set_next(GameState::LevelComplete).ok();
+            }
             _ => (),
         }
     }
+    // game events are handled only while playing: the level complete screen shows
+    // the finished board, next level is built once the player continues
+    if next_level {
+        game_events.send(GameEvent::ReloadLevel(1));
+    }
 }
 #[derive(Default)]
 pub struct UseItemState {
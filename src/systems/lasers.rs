@@ -19,7 +19,9 @@ pub fn move_laser_head(
         return;
     }
     let mut occupied = level_info.get_occupied(&queries.q1());
-    for (mut laser_head, mut position, mut dir) in queries.q0_mut().iter_mut() {
+    let mut laser_heads = queries.q0_mut().iter_mut().collect::<Vec<_>>();
+    laser_heads.sort_by_cached_key(|(_, pos, _)| pos.as_tuple());
+    for (mut laser_head, mut position, mut dir) in laser_heads {
         let new_pos = position.add(&*dir);
         if occupied.is_free(&new_pos) {
             let old_pos = *position;
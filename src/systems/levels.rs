@@ -18,7 +18,7 @@ pub fn asset_events(
     for event in state.reader.iter(&events) {
         match event {
             AssetEvent::Created {..}| AssetEvent::Modified { .. } => {
-                level_info.current_level = (opts.level - 1).max(0);
+                level_info.current_level = opts.level.unwrap_or(1).max(1) - 1;
                 game_events.send(GameEvent::ReloadLevel(0));
             }
             _ => continue,
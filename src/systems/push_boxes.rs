@@ -17,7 +17,9 @@ pub fn move_pushbox(
         return;
     }
     let mut occupied = level_info.get_occupied(queries.q0());
-    for (mut position, mut dir) in queries.q1_mut().iter_mut() {
+    let mut push_boxes = queries.q1_mut().iter_mut().collect::<Vec<_>>();
+    push_boxes.sort_by_cached_key(|(pos, _)| pos.as_tuple());
+    for (mut position, mut dir) in push_boxes {
         if damage_map.is_damaged(&*position) || dir.is_empty() {
             continue;
         }
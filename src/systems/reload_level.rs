@@ -2,8 +2,10 @@ use crate::components::prelude::*;
 use crate::entities::create_small_explosion;
 use crate::frame_cnt::FrameCnt;
 use crate::game_events::GameEvent;
+use crate::inventory::{Inventory, CLASSIC_LIVES};
 use crate::levels::{LevelInfo, LevelSet};
 use crate::plugins::audio::Sound;
+use crate::plugins::GameState;
 use std::time::Instant;
 
 use bevy::app::AppExit;
@@ -11,8 +13,12 @@ use bevy::prelude::*;
 
 pub fn reload_level(
     commands: &mut Commands,
-    mut level_info: ResMut<LevelInfo>,
-    frame_cnt: Res<FrameCnt>,
+    (mut level_info, mut inventory, mut game_state, frame_cnt): (
+        ResMut<LevelInfo>,
+        ResMut<Inventory>,
+        ResMut<State<GameState>>,
+        Res<FrameCnt>,
+    ),
     mut game_events: ResMut<Events<GameEvent>>,
     mut sounds: ResMut<Events<Sound>>,
     robbo_query: Query<Entity, With<Robbo>>,
@@ -33,7 +39,21 @@ pub fn reload_level(
             sounds.send(Sound::BOMB);
         }
     } else if level_info.missing_robbo_ticks == 20 {
-        game_events.send(GameEvent::ReloadLevel(0));
+        match inventory.lives {
+            // the attempt that just ended was the last one
+            Some(lives) if lives <= 1 => {
+                info!("game over");
+                game_state.set_next(GameState::GameOver).ok();
+                inventory.lives = Some(CLASSIC_LIVES);
+                let k = -(level_info.current_level as i32);
+                game_events.send(GameEvent::ReloadLevel(k));
+            }
+            Some(lives) => {
+                inventory.lives = Some(lives - 1);
+                game_events.send(GameEvent::ReloadLevel(0));
+            }
+            None => game_events.send(GameEvent::ReloadLevel(0)),
+        }
     }
 }
 
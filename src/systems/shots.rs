@@ -2,15 +2,17 @@ use crate::components::prelude::*;
 use crate::entities::*;
 use crate::frame_cnt::FrameCnt;
 use crate::levels::LevelInfo;
-use crate::resources::DamageMap;
+use crate::resources::{DamageMap, GameRng};
 use bevy::prelude::*;
-use rand::random;
 
 pub fn shot_system(
     commands: &mut Commands,
-    level_info: ResMut<LevelInfo>,
-    mut damage_map: ResMut<DamageMap>,
-    frame_cnt: Res<FrameCnt>,
+    (level_info, mut damage_map, mut rng, frame_cnt): (
+        ResMut<LevelInfo>,
+        ResMut<DamageMap>,
+        ResMut<GameRng>,
+        Res<FrameCnt>,
+    ),
     items: Query<(&Position, Entity), Without<Wall>>,
     shooting_items: Query<(&Position, &ShootingDir, &Gun, &ShootingProp)>,
     robbo_query: Query<Entity, With<Robbo>>,
@@ -19,8 +21,10 @@ pub fn shot_system(
         return;
     }
     let occupied = level_info.get_occupied(&items);
-    for (pos, dir, gun_type, prop) in &mut shooting_items.iter() {
-        if random::<f32>() >= prop.0 {
+    let mut shooting_items = shooting_items.iter().collect::<Vec<_>>();
+    shooting_items.sort_by_key(|(pos, ..)| pos.as_tuple());
+    for (pos, dir, gun_type, prop) in shooting_items {
+        if !rng.chance(prop.0) {
             continue;
         }
         let bullet_pos = pos.add(dir);
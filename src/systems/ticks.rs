@@ -2,12 +2,16 @@ use crate::components::prelude::*;
 use crate::entities::gun_set_shooting_dir;
 use crate::frame_cnt::FrameCnt;
 use crate::game_events::GameEvent;
+use crate::resources::GameRng;
 use bevy::prelude::*;
 
 pub fn tick_system(
     commands: &mut Commands,
-    frame_cnt: Res<FrameCnt>,
-    mut game_events: ResMut<Events<GameEvent>>,
+    (frame_cnt, mut rng, mut game_events): (
+        Res<FrameCnt>,
+        ResMut<GameRng>,
+        ResMut<Events<GameEvent>>,
+    ),
     mut items: Query<(Entity, &Position, &mut Tiles), Without<Wall>>,
     animations: Query<&Animation>,
     shooting_dirs: Query<(&Rotatable, &mut ShootingDir)>,
@@ -16,10 +20,12 @@ pub fn tick_system(
     if !frame_cnt.is_keyframe() {
         return;
     }
-    for (entity, _position, mut tiles) in items.iter_mut() {
-        tiles.current = (tiles.current + 1) % tiles.tiles.len();
+    let mut items = items.iter_mut().collect::<Vec<_>>();
+    items.sort_by_cached_key(|(_, pos, _)| pos.as_tuple());
+    for (entity, _position, mut tiles) in items {
+        tiles.current = (tiles.current + 1) % tiles.tiles().len();
         if let (true, Ok(animation)) = (
-            tiles.current == 0 && tiles.tiles.len() > 0,
+            tiles.current == 0 && !tiles.tiles().is_empty(),
             animations.get_component::<Animation>(entity),
         ) {
             info!("animation end");
@@ -28,7 +34,7 @@ pub fn tick_system(
                 game_events.send(*event);
             }
         } else if let Ok(rotatable) = rotatables.get_component::<Rotatable>(entity) {
-            if rand::random::<f32>() < 0.25 {
+            if rng.chance(0.25) {
                 let shooting_dir = shooting_dirs.get_component::<ShootingDir>(entity).unwrap();
                 match *rotatable {
                     Rotatable::Regular => {
@@ -42,7 +48,7 @@ pub fn tick_system(
                         gun_set_shooting_dir(
                             commands,
                             entity,
-                            ShootingDir::by_index(rand::random::<usize>() % 4),
+                            ShootingDir::by_index(rng.index(4)),
                         );
                     }
                 };
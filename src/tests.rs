@@ -0,0 +1,596 @@
+//! Golden-board regression tests: levels are run headless with fixed input and seed,
+//! board snapshots are compared with files in `tests/golden`.
+//! Run with `UPDATE_GOLDEN=1` to (re)write the golden files after intended rule changes.
+
+use crate::board::{board_lines, board_rows, info_lines, inventory_line};
+use crate::components::prelude::*;
+use crate::game_events::GameEvent;
+use crate::inventory::{Inventory, CLASSIC_LIVES};
+use crate::levels::{LevelInfo, LevelSet};
+use crate::plugins::replay::{Replay, ReplayFrame};
+use crate::plugins::controls::{Action, Bindings, ControlsEditor};
+use crate::plugins::gamepad::GamepadInput;
+use crate::plugins::keyboard::{keyboard_system, robbo_facing, robbo_tiles, InputQueue};
+use crate::plugins::progress::{Progress, ProgressStorage};
+use crate::plugins::render::viewport_offset;
+use crate::plugins::rewind::History;
+use crate::plugins::{FrameCnt, FrameCntPlugin, GameState, ProgressPlugin, RewindPlugin};
+use crate::plugins::simulation::{gameplay_stage, Simulation};
+use crate::plugins::touch::TouchControls;
+use crate::snapshot::Snapshot;
+use crate::storage::Storage;
+use crate::verify::{run_replay, Outcome};
+use bevy::prelude::*;
+use std::path::PathBuf;
+
+static ORIGINAL: &str = include_str!("../assets/original.txt");
+static FOREVER: &str = include_str!("../assets/forever.txt");
+
+fn snapshot(sim: &Simulation, keyframe: usize) -> String {
+    let resources = &sim.app.resources;
+    let level_info = resources.get::<LevelInfo>().unwrap();
+    let inventory = resources.get::<Inventory>().unwrap();
+    let mut out = format!(
+        "[keyframe]\n{}\n[inventory]\nscrews {} keys {} bullets {}\n[data]\n",
+        keyframe, inventory.screws, inventory.keys, inventory.bullets
+    );
+    for row in board_rows(&sim.app.world, &level_info) {
+        out.push_str(&row);
+        out.push('\n');
+    }
+    out
+}
+
+fn run(levelset: &str, level: usize, frames: &[ReplayFrame], keyframes: &[usize]) -> String {
+    let mut sim = Simulation::new(LevelSet::new(levelset), replay(level, frames.to_vec()));
+    let mut out = String::new();
+    let mut tick = 0;
+    for &keyframe in keyframes {
+        while tick < keyframe {
+            sim.step();
+            tick += 1;
+        }
+        out.push_str(&snapshot(&sim, keyframe));
+    }
+    out
+}
+
+fn check_golden(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing golden file {:?}, run with UPDATE_GOLDEN=1", path));
+    assert!(
+        expected == actual,
+        "board differs from {:?}\n--- expected\n{}\n--- actual\n{}",
+        path,
+        expected,
+        actual
+    );
+}
+
+fn idle(n: usize) -> Vec<ReplayFrame> {
+    vec![ReplayFrame::default(); n]
+}
+
+fn walk(kx: i32, ky: i32) -> ReplayFrame {
+    ReplayFrame {
+        dir: MovingDir::new(kx, ky),
+        ..Default::default()
+    }
+}
+
+fn shoot(kx: i32, ky: i32) -> ReplayFrame {
+    ReplayFrame {
+        shot: Some(ShootingDir::new(kx, ky)),
+        ..Default::default()
+    }
+}
+
+fn replay(level: usize, frames: Vec<ReplayFrame>) -> Replay {
+    Replay {
+        levelset_path: PathBuf::new(),
+        level,
+        seed: 1234,
+        frames,
+    }
+}
+
+/// levels 1 and 2, both with the given rows
+fn tiny_level_set(rows: &[&str]) -> LevelSet {
+    let level = |n| {
+        format!(
+            "[level]\n{}\n[size]\n{}.{}\n[data]\n{}\n[end]\n",
+            n,
+            rows[0].len(),
+            rows.len(),
+            rows.join("\n")
+        )
+    };
+    LevelSet::new(&(level(1) + &level(2)))
+}
+
+const CHECKPOINTS: &[usize] = &[10, 20, 40, 80];
+
+#[test]
+fn golden_original_level_1_guns() {
+    check_golden("original_01", &run(ORIGINAL, 1, &idle(80), CHECKPOINTS));
+}
+
+#[test]
+fn golden_original_level_4_bears() {
+    check_golden("original_04", &run(ORIGINAL, 4, &idle(80), CHECKPOINTS));
+}
+
+#[test]
+fn golden_original_level_12_magnets_and_eyes() {
+    check_golden("original_12", &run(ORIGINAL, 12, &idle(80), CHECKPOINTS));
+}
+
+#[test]
+fn golden_original_level_49_force_fields() {
+    check_golden("original_49", &run(ORIGINAL, 49, &idle(80), CHECKPOINTS));
+}
+
+#[test]
+fn golden_forever_level_53_laser() {
+    check_golden("forever_53", &run(FOREVER, 53, &idle(80), CHECKPOINTS));
+}
+
+#[test]
+fn golden_original_level_1_walk_and_shoot() {
+    // Robbo appears after 9 keyframes, collects the ammo and shoots it back
+    let mut frames = idle(10);
+    frames.extend(vec![walk(0, 1); 2]);
+    frames.push(shoot(0, -1));
+    frames.extend(idle(3));
+    frames.push(shoot(1, 0));
+    check_golden(
+        "original_01_walk_and_shoot",
+        &run(ORIGINAL, 1, &frames, &[12, 14, 16, 20, 30]),
+    );
+}
+
+#[test]
+fn golden_millenium_and_alex_objects() {
+    // Robbo collects the ammo and shoots Bomb2, which fires back at him
+    let level = "[level]\n1\n[size]\n7.5\n[data]\nOOOOOOO\nO'X.#.O\nOR.B.kO\nO.....O\nOOOOOOO\n[end]\n";
+    let mut frames = idle(10);
+    frames.push(walk(-1, 0));
+    frames.push(walk(1, 0));
+    frames.push(shoot(0, 1));
+    check_golden(
+        "millenium_and_alex",
+        &run(level, 1, &frames, &[10, 11, 12, 13, 14, 16, 18, 20, 22, 26]),
+    );
+}
+
+#[test]
+fn replay_shoots_only_with_ammo() {
+    let fired = |bullets| {
+        let level_set = tiny_level_set(&["OOOOO", "O...O", "O.R.O", "O...O", "OOOOO"]);
+        let mut frames = idle(10);
+        frames.push(shoot(1, 0));
+        let mut sim = Simulation::new(level_set, replay(1, frames));
+        for _ in 0..10 {
+            sim.step();
+        }
+        sim.app.resources.get_mut::<Inventory>().unwrap().bullets = bullets;
+        let mut fired = false;
+        for _ in 0..3 {
+            sim.step();
+            fired |= sim.app.world.query::<&Bullet>().next().is_some();
+        }
+        (fired, sim.app.resources.get::<Inventory>().unwrap().bullets)
+    };
+    assert_eq!(fired(1), (true, 0));
+    assert_eq!(fired(0), (false, 0));
+}
+
+#[test]
+fn same_seed_and_input_give_same_board() {
+    let keyframes: Vec<usize> = (1..=60).collect();
+    for level in &[1, 12, 49] {
+        assert_eq!(
+            run(ORIGINAL, *level, &idle(60), &keyframes),
+            run(ORIGINAL, *level, &idle(60), &keyframes)
+        );
+    }
+}
+
+#[test]
+fn classic_mode_lives_and_game_over() {
+    let level_set = tiny_level_set(&["OOO", "OR+", "OOO"]);
+    let mut frames = idle(10);
+    frames.push(walk(0, 1));
+    frames.push(ReplayFrame {
+        kill: true,
+        ..Default::default()
+    });
+    let mut sim = Simulation::new(level_set, replay(2, frames));
+    sim.app.resources.insert(Inventory {
+        lives: Some(1),
+        ..Default::default()
+    });
+    let steps = |sim: &mut Simulation, n| {
+        for _ in 0..n {
+            sim.step();
+        }
+        let resources = &sim.app.resources;
+        let lives = resources.get::<Inventory>().unwrap().lives;
+        (lives, resources.get::<LevelInfo>().unwrap().current_level)
+    };
+    // extra life collected
+    assert_eq!(steps(&mut sim, 12), (Some(2), 1));
+    // killed, the level restarts with one life less
+    assert_eq!(steps(&mut sim, 30), (Some(1), 1));
+    // killed again when respawned: that was the last life, game starts from the first level
+    steps(&mut sim, 10);
+    sim.app
+        .resources
+        .get_mut::<Events<GameEvent>>()
+        .unwrap()
+        .send(GameEvent::KillRobbo);
+    assert_eq!(steps(&mut sim, 30), (Some(CLASSIC_LIVES), 0));
+    assert_eq!(game_state(&sim), GameState::GameOver);
+}
+
+fn game_state(sim: &Simulation) -> GameState {
+    *sim.app.resources.get::<State<GameState>>().unwrap().current()
+}
+
+fn frame_cnt(sim: &Simulation) -> usize {
+    sim.app.resources.get::<FrameCnt>().unwrap().value()
+}
+
+#[test]
+fn board_is_frozen_unless_playing() {
+    let level_set = tiny_level_set(&["OOOO", "OR!O", "OOOO"]);
+    let mut frames = idle(10);
+    frames.push(walk(0, 1));
+    let mut sim = Simulation::new(level_set, replay(1, frames));
+    for _ in 0..5 {
+        sim.step();
+    }
+    sim.app
+        .resources
+        .get_mut::<State<GameState>>()
+        .unwrap()
+        .set_next(GameState::Paused)
+        .unwrap();
+    sim.step();
+    let (paused_at, board) = (frame_cnt(&sim), snapshot(&sim, 0));
+    for _ in 0..10 {
+        sim.step();
+    }
+    assert_eq!(game_state(&sim), GameState::Paused);
+    assert_eq!((frame_cnt(&sim), snapshot(&sim, 0)), (paused_at, board));
+
+    // Robbo enters the capsule, next level waits behind level complete screen
+    sim.app
+        .resources
+        .get_mut::<State<GameState>>()
+        .unwrap()
+        .set_next(GameState::Playing)
+        .unwrap();
+    for _ in 0..30 {
+        sim.step();
+    }
+    assert_eq!(game_state(&sim), GameState::LevelComplete);
+    let level_info = sim.app.resources.get::<LevelInfo>().unwrap();
+    assert_eq!(level_info.current_level, 1);
+}
+
+#[test]
+fn rewind_restores_earlier_keyframes() {
+    for level in &[1, 12, 49] {
+        let level_set = LevelSet::new(ORIGINAL);
+        let mut sim = Simulation::with_plugins(level_set, replay(*level, idle(100)), |app| {
+            app.add_plugin(RewindPlugin);
+        });
+        let mut boards = vec![];
+        for _ in 0..30 {
+            sim.step();
+            boards.push((frame_cnt(&sim), snapshot(&sim, 0)));
+        }
+        let set_rewind = |sim: &mut Simulation, rewind| {
+            sim.app.resources.get_mut::<History>().unwrap().rewind = rewind;
+        };
+        // one keyframe back per keyframe
+        set_rewind(&mut sim, true);
+        for k in (24..29).rev() {
+            sim.step();
+            assert!((frame_cnt(&sim), snapshot(&sim, 0)) == boards[k], "level {}", level);
+        }
+        // same input and the same random numbers once again
+        set_rewind(&mut sim, false);
+        for board in &boards[25..] {
+            sim.step();
+            assert!((frame_cnt(&sim), snapshot(&sim, 0)) == *board, "level {}", level);
+        }
+    }
+}
+
+#[test]
+fn progress_is_saved_when_level_is_completed() {
+    let level_set = tiny_level_set(&["OOOO", "OR!O", "OOOO"]);
+    let mut frames = idle(10);
+    frames.push(walk(0, 1));
+    let save_dir = std::env::temp_dir().join(format!("robbo-progress-{}", std::process::id()));
+    std::fs::create_dir_all(&save_dir).unwrap();
+    let storage = Storage::new(&save_dir, &PathBuf::from("test.txt"), "progress");
+    let mut progress = Progress::default();
+    progress.best_ticks.insert(1, 1000);
+    let plugin = ProgressPlugin {
+        storage: ProgressStorage(storage.clone()),
+        progress,
+    };
+    let mut sim = Simulation::with_plugins(level_set, replay(1, frames), |app| {
+        app.add_plugin(plugin);
+    });
+    for _ in 0..30 {
+        sim.step();
+    }
+    let progress = Progress::load(&storage);
+    std::fs::remove_dir_all(&save_dir).unwrap();
+    assert_eq!((progress.current_level, progress.unlocked_level), (2, 2));
+    let ticks = progress.best_ticks[&1];
+    assert!(ticks > 10 && ticks < 30, "{}", ticks);
+    assert_eq!(Progress::parse(&progress.to_string()).unwrap(), progress);
+}
+
+#[test]
+fn saved_board_is_restored_exactly() {
+    for level in &[1, 12, 49] {
+        let seeded = |seed| Replay {
+            seed,
+            ..replay(*level, idle(100))
+        };
+        let mut sim = Simulation::new(LevelSet::new(ORIGINAL), seeded(1234));
+        for _ in 0..20 {
+            sim.step();
+        }
+        let saved = Snapshot::capture_full(&sim.app.world, &sim.app.resources).to_string();
+        let board = Snapshot::parse(&saved).unwrap();
+        assert_eq!(board.to_string(), saved);
+
+        // another level attempt with different random numbers
+        let mut loaded = Simulation::new(LevelSet::new(ORIGINAL), seeded(1));
+        for _ in 0..5 {
+            loaded.step();
+        }
+        board.restore(&mut loaded.app.world, &loaded.app.resources);
+        for _ in 0..20 {
+            sim.step();
+            loaded.step();
+            assert!(snapshot(&loaded, 0) == snapshot(&sim, 0), "level {}", level);
+        }
+    }
+}
+
+#[test]
+fn controls_are_parsed_and_written_back() {
+    let bindings = Bindings::parse("[fire]\nSpace LControl\n\n[kill]\nK\n").unwrap();
+    assert_eq!(bindings.keys(Action::Fire), &[KeyCode::Space, KeyCode::LControl]);
+    assert_eq!(bindings.keys(Action::Kill), &[KeyCode::K]);
+    // missing actions keep their default keys
+    assert_eq!(bindings.keys(Action::Up), Bindings::default().keys(Action::Up));
+    assert_eq!(Bindings::parse(&bindings.to_string()).unwrap(), bindings);
+
+    assert!(Bindings::parse("[jump]\nSpace\n").is_err());
+    assert!(Bindings::parse("[fire]\nHyper\n").is_err());
+}
+
+#[test]
+fn controls_editor_binds_several_keys_and_refuses_used_ones() {
+    let mut bindings = Bindings::default();
+    let mut editor = ControlsEditor::default();
+    editor.start(&bindings);
+    // up: two new keys, Space is refused as it confirms
+    for &key in &[KeyCode::I, KeyCode::Space, KeyCode::Up, KeyCode::F2] {
+        assert!(!editor.press(&mut bindings, key));
+    }
+    assert_eq!(bindings.keys(Action::Up), &[KeyCode::I, KeyCode::Up]);
+    // the rest keeps the current keys
+    let mut finished = false;
+    for _ in 1..Action::ALL.len() {
+        assert!(!finished);
+        finished = editor.press(&mut bindings, KeyCode::F2);
+    }
+    assert!(finished);
+    assert_eq!(bindings.keys(Action::Down), &[KeyCode::Down, KeyCode::S]);
+    assert_eq!(bindings.keys(Action::Confirm), &[KeyCode::Return, KeyCode::Space]);
+    assert_eq!(editor.prompt, None);
+}
+
+/// board played with the keyboard, keyframe every second step; Robbo has appeared
+/// and the next step is a keyframe
+fn keyboard_simulation() -> Simulation {
+    let level_set = tiny_level_set(&["OOOOO", "O...O", "O.R.O", "O...O", "OOOOO"]);
+    let mut sim = Simulation::with_key_frame_interval(2, level_set, replay(1, vec![]), |app| {
+        app.add_resource(Input::<KeyCode>::default())
+            .add_resource(Bindings::default())
+            .add_resource(GamepadInput::default())
+            .add_resource(TouchControls::default())
+            .add_resource(InputQueue::default())
+            .add_stage_after(stage::EVENT, "keyboard", gameplay_stage())
+            .add_system_to_stage("keyboard", keyboard_system.system());
+    });
+    for _ in 0..30 {
+        sim.step();
+    }
+    sim
+}
+
+/// one step with only `keys` held, all of them just pressed; returns the last applied input
+fn press(sim: &mut Simulation, keys: &[KeyCode]) -> ReplayFrame {
+    {
+        let mut input = sim.app.resources.get_mut::<Input<KeyCode>>().unwrap();
+        let held: Vec<_> = input.get_pressed().copied().collect();
+        for key in held {
+            input.release(key);
+        }
+        input.update();
+        for key in keys {
+            input.press(*key);
+        }
+    }
+    sim.step();
+    sim.app.resources.get::<InputQueue>().unwrap().applied
+}
+
+#[test]
+fn taps_between_keyframes_are_applied_in_order() {
+    let mut sim = keyboard_simulation();
+    sim.app.resources.get_mut::<Inventory>().unwrap().bullets = 1;
+    press(&mut sim, &[]);
+    // shot and move tapped before the next keyframe, one applied per keyframe
+    press(&mut sim, &[KeyCode::LShift, KeyCode::Left]);
+    assert_eq!(press(&mut sim, &[KeyCode::Up]), shoot(-1, 0));
+    assert_eq!(sim.app.resources.get::<Inventory>().unwrap().bullets, 0);
+    press(&mut sim, &[]);
+    assert_eq!(press(&mut sim, &[]), walk(0, 1));
+}
+
+#[test]
+fn input_queue_is_limited_and_cleared_by_kill() {
+    let mut sim = keyboard_simulation();
+    press(&mut sim, &[]);
+    press(&mut sim, &[KeyCode::Right, KeyCode::Left, KeyCode::Up, KeyCode::Down]);
+    // the queue is full, this tap is dropped
+    assert_eq!(press(&mut sim, &[KeyCode::Left]), walk(1, 0));
+    for expected in &[walk(-1, 0), walk(0, 1), walk(0, -1), ReplayFrame::default()] {
+        press(&mut sim, &[]);
+        assert_eq!(press(&mut sim, &[]), *expected);
+    }
+    // taps made before the kill are forgotten
+    press(&mut sim, &[KeyCode::Left, KeyCode::Up]);
+    let kill = ReplayFrame {
+        kill: true,
+        ..Default::default()
+    };
+    assert_eq!(press(&mut sim, &[KeyCode::Escape]), kill);
+    press(&mut sim, &[]);
+    assert_eq!(press(&mut sim, &[]), ReplayFrame::default());
+}
+
+#[test]
+fn robbo_faces_the_direction_of_the_last_move() {
+    let mut tiles = Tiles::new(&[60]);
+    assert_eq!(robbo_facing(&tiles), Some((1, 0)));
+    for &(kx, ky) in &[(-1, 0), (0, 1), (0, -1), (1, 0)] {
+        tiles = robbo_tiles(kx, ky, &tiles);
+        assert_eq!(robbo_facing(&tiles), Some((kx, ky)));
+        // standing still keeps the orientation
+        tiles = robbo_tiles(0, 0, &tiles);
+        assert_eq!(robbo_facing(&tiles), Some((kx, ky)));
+    }
+}
+
+#[test]
+fn keyframes_follow_real_time_at_any_fps() {
+    for &fps in &[30.0, 60.0, 144.0, 7.0] {
+        let mut builder = App::build();
+        builder.add_plugin(FrameCntPlugin::new(8).with_real_time());
+        let mut frame_cnt = builder.resources_mut().get_mut::<FrameCnt>().unwrap();
+        let mut keyframes = 0;
+        // 4 seconds, 7.5 keyframes per second
+        for _ in 0..(4.0 * fps) as usize {
+            frame_cnt.advance(1.0 / fps);
+            keyframes += frame_cnt.is_keyframe() as usize;
+            assert!(frame_cnt.keyframe_progress() < 1.0);
+        }
+        let expected = if fps < 7.5 { 4 * fps as usize } else { 30 };
+        assert!(
+            (keyframes as i32 - expected as i32).abs() <= 1,
+            "{} keyframes at {} fps",
+            keyframes,
+            fps
+        );
+    }
+}
+
+#[test]
+fn text_frontends_show_board_as_in_window() {
+    let level_set = LevelSet::new(
+        "[name]\nTiny\n[level]\n1\n[author]\nMe\n[level_notes]\nMind the bird\n\
+         [size]\n5.4\n[data]\nOOOOO\nOR'.O\nO.+TO\nOOOOO\n[end]\n",
+    );
+    let mut sim = Simulation::new(level_set, replay(0, idle(10)));
+    for _ in 0..10 {
+        sim.step();
+    }
+    let resources = &sim.app.resources;
+    let level_info = resources.get::<LevelInfo>().unwrap();
+    // level file rows are columns of the window, first one on the left
+    assert_eq!(
+        board_lines(&sim.app.world, &level_info),
+        vec!["OOOO", "O.TO", "O'+O", "OR.O", "OOOO"]
+    );
+    assert_eq!(inventory_line(resources), "screws 1  keys 0  ammo 0");
+    assert_eq!(
+        info_lines(resources),
+        vec!["Tiny - level 1 by Me", "Mind the bird"]
+    );
+}
+
+#[test]
+fn viewport_follows_robbo_within_level_bounds() {
+    // levels that fit never scroll
+    assert_eq!(viewport_offset((30, 15), (31, 16)), Vec2::zero());
+    // 41x26 level: 10 columns and rows to scroll, Robbo in the middle of the viewport
+    assert_eq!(viewport_offset((20, 12), (41, 26)), Vec2::new(5.0, 4.5) * 32.0);
+    assert_eq!(viewport_offset((0, 0), (41, 26)), Vec2::zero());
+    assert_eq!(viewport_offset((40, 25), (41, 26)), Vec2::new(10.0, 10.0) * 32.0);
+}
+
+#[test]
+fn verify_reports_completed_level() {
+    let level_set = tiny_level_set(&["OOOO", "OR!O", "OOOO"]);
+    let mut frames = idle(10);
+    frames.push(walk(0, 1));
+    match run_replay(level_set, replay(1, frames), 30) {
+        Outcome::Completed(ticks) => assert!(ticks > 10 && ticks < 30, "{}", ticks),
+        outcome => panic!("{:?}", outcome),
+    }
+}
+
+#[test]
+fn verify_reports_restarted_level_as_died() {
+    let level_set = tiny_level_set(&["OOOO", "OR!O", "OOOO"]);
+    let mut frames = idle(10);
+    frames.push(ReplayFrame {
+        kill: true,
+        ..Default::default()
+    });
+    match run_replay(level_set, replay(1, frames), 60) {
+        Outcome::Died(ticks) => assert!(ticks > 10 && ticks < 60, "{}", ticks),
+        outcome => panic!("{:?}", outcome),
+    }
+}
+
+#[test]
+fn verify_reports_level_change() {
+    let level_set = tiny_level_set(&["OOOO", "OR!O", "OOOO"]);
+    let mut frames = idle(10);
+    frames.push(ReplayFrame {
+        level_change: 1,
+        ..Default::default()
+    });
+    match run_replay(level_set, replay(1, frames), 60) {
+        Outcome::LevelChanged(ticks) => assert!(ticks > 10 && ticks < 60, "{}", ticks),
+        outcome => panic!("{:?}", outcome),
+    }
+}
+
+#[test]
+fn verify_times_out_when_robbo_idles() {
+    let level_set = tiny_level_set(&["OOOO", "OR!O", "OOOO"]);
+    assert_eq!(run_replay(level_set, replay(1, idle(10)), 40), Outcome::TimedOut(40));
+}
@@ -0,0 +1,292 @@
+use crate::board::{board_lines, info_lines, inventory_line};
+use crate::levels::LevelInfo;
+use crate::plugins::touch::TouchControls;
+use bevy::app::{AppExit, ScheduleRunnerSettings};
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::ElementState;
+use bevy::prelude::*;
+use crossterm::event::{self, Event, KeyEvent, KeyModifiers};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use std::collections::HashMap;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Board drawn with characters in the terminal, input read from it.
+/// Replaces window, rendering and winit (works over SSH, without GPU).
+pub struct TtyPlugin {
+    pub fps: usize,
+    pub key_hold_time: Duration,
+}
+
+/// Terminals report key presses (and auto repeats) only, a key is released when it
+/// isn't repeated for this long. Must be longer than the terminal's auto repeat delay,
+/// otherwise a held key is released and pressed again before it starts repeating.
+pub struct KeyHoldTime(pub Duration);
+
+impl Plugin for TtyPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1.0 / self.fps as f64,
+        )))
+        .add_plugin(bevy::reflect::ReflectPlugin)
+        .add_plugin(bevy::core::CorePlugin)
+        .add_plugin(bevy::app::ScheduleRunnerPlugin {})
+        .add_plugin(bevy::asset::AssetPlugin)
+        .add_plugin(bevy::input::InputPlugin)
+        .add_plugin(bevy::window::WindowPlugin::default())
+        // no touch screen, but keyboard systems read it
+        .add_resource(TouchControls::default())
+        .add_resource(KeyHoldTime(self.key_hold_time))
+        .init_resource::<TtyScreen>()
+        .add_stage_before(stage::EVENT, "tty_input", SystemStage::parallel())
+        .add_system_to_stage("tty_input", tty_input_system.system())
+        .add_system_to_stage(stage::LAST, tty_render_system.system());
+        #[cfg(feature = "audio")]
+        app.add_plugin(bevy::audio::AudioPlugin);
+    }
+}
+
+/// Raw mode and alternate screen for the lifetime of the game, restored on drop (also on panic)
+pub struct Terminal;
+
+impl Terminal {
+    pub fn new() -> crossterm::Result<Terminal> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            std::io::stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            terminal::Clear(terminal::ClearType::All)
+        )?;
+        Ok(Terminal)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(
+            std::io::stdout(),
+            ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn key_code(key: event::KeyCode) -> Option<KeyCode> {
+    use event::KeyCode as K;
+    Some(match key {
+        K::Up => KeyCode::Up,
+        K::Down => KeyCode::Down,
+        K::Left => KeyCode::Left,
+        K::Right => KeyCode::Right,
+        K::Esc => KeyCode::Escape,
+        K::Enter => KeyCode::Return,
+        K::Backspace => KeyCode::Back,
+        K::Tab => KeyCode::Tab,
+        K::PageUp => KeyCode::PageUp,
+        K::PageDown => KeyCode::PageDown,
+        K::Home => KeyCode::Home,
+        K::End => KeyCode::End,
+        K::Insert => KeyCode::Insert,
+        K::Delete => KeyCode::Delete,
+        K::F(n) => [
+            KeyCode::F1,
+            KeyCode::F2,
+            KeyCode::F3,
+            KeyCode::F4,
+            KeyCode::F5,
+            KeyCode::F6,
+            KeyCode::F7,
+            KeyCode::F8,
+            KeyCode::F9,
+            KeyCode::F10,
+            KeyCode::F11,
+            KeyCode::F12,
+        ]
+        .get((n as usize).checked_sub(1)?)
+        .copied()?,
+        K::Char(' ') => KeyCode::Space,
+        K::Char(c) => {
+            let letters = [
+                KeyCode::A,
+                KeyCode::B,
+                KeyCode::C,
+                KeyCode::D,
+                KeyCode::E,
+                KeyCode::F,
+                KeyCode::G,
+                KeyCode::H,
+                KeyCode::I,
+                KeyCode::J,
+                KeyCode::K,
+                KeyCode::L,
+                KeyCode::M,
+                KeyCode::N,
+                KeyCode::O,
+                KeyCode::P,
+                KeyCode::Q,
+                KeyCode::R,
+                KeyCode::S,
+                KeyCode::T,
+                KeyCode::U,
+                KeyCode::V,
+                KeyCode::W,
+                KeyCode::X,
+                KeyCode::Y,
+                KeyCode::Z,
+            ];
+            let digits = [
+                KeyCode::Key0,
+                KeyCode::Key1,
+                KeyCode::Key2,
+                KeyCode::Key3,
+                KeyCode::Key4,
+                KeyCode::Key5,
+                KeyCode::Key6,
+                KeyCode::Key7,
+                KeyCode::Key8,
+                KeyCode::Key9,
+            ];
+            let c = c.to_ascii_lowercase();
+            match c {
+                'a'..='z' => letters[(c as u8 - b'a') as usize],
+                '0'..='9' => digits[(c as u8 - b'0') as usize],
+                _ => return None,
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// keys (with modifiers) of a terminal key event
+fn pressed_keys(event: KeyEvent) -> Vec<KeyCode> {
+    let mut keys: Vec<_> = key_code(event.code).into_iter().collect();
+    if event.modifiers.contains(KeyModifiers::SHIFT) {
+        keys.push(KeyCode::LShift);
+    }
+    if event.modifiers.contains(KeyModifiers::CONTROL) {
+        keys.push(KeyCode::LControl);
+    }
+    keys
+}
+
+fn keyboard_event(key_code: KeyCode, state: ElementState) -> KeyboardInput {
+    KeyboardInput {
+        scan_code: 0,
+        key_code: Some(key_code),
+        state,
+    }
+}
+
+/// Turns terminal key events into bevy keyboard events, so bindings work as with a window.
+/// Ctrl+C or Ctrl+Q quits.
+pub fn tty_input_system(
+    mut held: Local<HashMap<KeyCode, Instant>>,
+    key_hold_time: Res<KeyHoldTime>,
+    mut keyboard_events: ResMut<Events<KeyboardInput>>,
+    mut app_exit: ResMut<Events<AppExit>>,
+) {
+    let now = Instant::now();
+    while let Ok(true) = event::poll(Duration::from_secs(0)) {
+        let key_event = match event::read() {
+            Ok(Event::Key(key_event)) => key_event,
+            _ => continue,
+        };
+        let is_ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        if is_ctrl && matches!(key_event.code, event::KeyCode::Char('c' | 'q')) {
+            app_exit.send(AppExit);
+            return;
+        }
+        for key in pressed_keys(key_event) {
+            if held.insert(key, now).is_none() {
+                keyboard_events.send(keyboard_event(key, ElementState::Pressed));
+            }
+        }
+    }
+    held.retain(|key, last_seen| {
+        let is_held = now.duration_since(*last_seen) < key_hold_time.0;
+        if !is_held {
+            keyboard_events.send(keyboard_event(*key, ElementState::Released));
+        }
+        is_held
+    });
+}
+
+/// text of a board symbol, two characters wide since terminal cells are about twice as tall
+fn cell_text(symbol: char) -> String {
+    match symbol {
+        'O' | 'o' | '-' | 'Q' | 'q' | 'p' | 'P' | 's' | 'S' => "██".to_string(),
+        'H' => "░░".to_string(),
+        '=' => "==".to_string(),
+        '.' => "  ".to_string(),
+        _ => format!("{} ", symbol),
+    }
+}
+
+fn cell_color(symbol: char) -> Color {
+    match symbol {
+        'O' | 'o' | '-' | 'Q' | 'q' | 'p' | 'P' | 's' | 'S' => Color::Blue,
+        'H' => Color::DarkGreen,
+        'R' | ':' | ',' => Color::White,
+        'T' | '%' | '\'' => Color::Yellow,
+        '+' | '!' | 'k' => Color::Green,
+        '@' | '*' | '^' | 'V' | 'b' | 'B' => Color::Red,
+        'D' | '~' | '#' => Color::DarkYellow,
+        '&' => Color::Magenta,
+        '?' | '=' | 'M' | '}' => Color::Cyan,
+        _ => Color::Grey,
+    }
+}
+
+/// what was drawn last time, the screen is redrawn only when it changes
+#[derive(Default)]
+pub struct TtyScreen {
+    rows: Vec<String>,
+    status: String,
+}
+
+/// Draws the board, inventory and game state prompt
+pub fn tty_render_system(world: &mut World, resources: &mut Resources) {
+    let rows = board_lines(world, &resources.get::<LevelInfo>().unwrap());
+    let mut status = vec![inventory_line(resources)];
+    status.extend(info_lines(resources));
+    let status = status.join("\r\n");
+
+    let mut screen = resources.get_mut::<TtyScreen>().unwrap();
+    if screen.rows == rows && screen.status == status {
+        return;
+    }
+    if let Err(err) = draw(&rows, &status) {
+        // nothing else can be shown: the terminal is gone (e.g. SSH session dropped)
+        warn!("cannot draw on terminal: {}", err);
+        resources.get_mut::<Events<AppExit>>().unwrap().send(AppExit);
+        return;
+    }
+    screen.rows = rows;
+    screen.status = status;
+}
+
+fn draw(rows: &[String], status: &str) -> crossterm::Result<()> {
+    let mut stdout = std::io::stdout();
+    queue!(
+        stdout,
+        cursor::MoveTo(0, 0),
+        terminal::Clear(terminal::ClearType::All)
+    )?;
+    for row in rows {
+        for symbol in row.chars() {
+            queue!(
+                stdout,
+                SetForegroundColor(cell_color(symbol)),
+                Print(cell_text(symbol))
+            )?;
+        }
+        queue!(stdout, ResetColor, Print("\r\n"))?;
+    }
+    queue!(stdout, Print("\r\n"), Print(status))?;
+    stdout.flush()?;
+    Ok(())
+}
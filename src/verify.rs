@@ -0,0 +1,139 @@
+use crate::components::{Robbo, Usable};
+use crate::game_events::GameEvent;
+use crate::levels::{LevelInfo, LevelSet};
+use crate::plugins::replay::Replay;
+use crate::plugins::simulation::Simulation;
+use anyhow::Context;
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct VerifyOpts {
+    /// replay files to run
+    #[structopt(required = true)]
+    pub replays: Vec<PathBuf>,
+
+    /// levelset to use instead of the one stored in the replay
+    #[structopt(long)]
+    pub levelset_path: Option<PathBuf>,
+
+    /// level to use instead of the one stored in the replay
+    #[structopt(long)]
+    pub level: Option<usize>,
+
+    /// give up after this many keyframes (default: replay length + 100)
+    #[structopt(long)]
+    pub max_ticks: Option<usize>,
+
+    /// fail on broken levels instead of skipping them
+    #[structopt(long)]
+    pub strict: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Outcome {
+    /// Robbo entered repaired capsule
+    Completed(usize),
+    /// Robbo was destroyed (or level was restarted), at the first keyframe without Robbo
+    Died(usize),
+    /// next / previous level was requested before completing this one
+    LevelChanged(usize),
+    TimedOut(usize),
+}
+
+/// levelset paths are relative to the assets directory, like for the game itself
+fn load_level_set(path: &Path, strict: bool) -> anyhow::Result<LevelSet> {
+    let asset_path = Path::new("assets").join(path);
+    let path = if path.exists() { path } else { &asset_path };
+    let data = std::fs::read_to_string(path)
+        .with_context(|| format!("cannot read levelset {:?}", path))?;
+    if strict {
+        return LevelSet::parse(&data).with_context(|| format!("invalid levelset {:?}", path));
+    }
+    let level_set = LevelSet::new(&data);
+    for err in &level_set.errors {
+        eprintln!("{}: level skipped, {}", path.display(), err);
+    }
+    Ok(level_set)
+}
+
+pub fn run_replay(level_set: LevelSet, replay: Replay, max_ticks: usize) -> Outcome {
+    let mut sim = Simulation::new(level_set, replay);
+    let mut reader = sim
+        .app
+        .resources
+        .get::<Events<GameEvent>>()
+        .unwrap()
+        .get_reader();
+    let mut start_frame = None;
+    let mut level = 0;
+    let mut robbo_seen_at = 0;
+    for tick in 1..=max_ticks {
+        sim.step();
+        let world = &sim.app.world;
+        let resources = &sim.app.resources;
+        if world.query::<&Robbo>().next().is_some() {
+            robbo_seen_at = tick;
+        }
+        let events = resources.get::<Events<GameEvent>>().unwrap();
+        for event in reader.iter(&events) {
+            if let GameEvent::Use(entity, ..) = *event {
+                if let Ok(Usable::Capsule) = world.get::<Usable>(entity) {
+                    return Outcome::Completed(tick);
+                }
+            }
+        }
+        let level_info = resources.get::<LevelInfo>().unwrap();
+        if start_frame.is_none() {
+            start_frame = level_info.start_frame;
+            level = level_info.current_level;
+        } else if start_frame != level_info.start_frame {
+            if level_info.current_level != level {
+                return Outcome::LevelChanged(tick);
+            }
+            return Outcome::Died(robbo_seen_at + 1);
+        }
+    }
+    Outcome::TimedOut(max_ticks)
+}
+
+/// Runs every replay headlessly, prints the result; returns process exit code
+pub fn run(opts: &VerifyOpts) -> i32 {
+    let mut exit_code = 0;
+    for path in &opts.replays {
+        let result = Replay::load(path).and_then(|mut replay| {
+            if let Some(levelset_path) = opts.levelset_path.as_ref() {
+                replay.levelset_path = levelset_path.clone();
+            }
+            if let Some(level) = opts.level {
+                replay.level = level;
+            }
+            let level_set = load_level_set(&replay.levelset_path, opts.strict)?;
+            let max_ticks = opts.max_ticks.unwrap_or(replay.frames.len() + 100);
+            Ok((replay.level, run_replay(level_set, replay, max_ticks)))
+        });
+        match result {
+            Ok((level, Outcome::Completed(ticks))) => {
+                println!("{}: level {} completed in {} ticks", path.display(), level, ticks);
+            }
+            Ok((level, Outcome::Died(ticks))) => {
+                println!("{}: level {} failed, Robbo died after {} ticks", path.display(), level, ticks);
+                exit_code = 1;
+            }
+            Ok((level, Outcome::LevelChanged(ticks))) => {
+                println!("{}: level {} failed, level changed after {} ticks", path.display(), level, ticks);
+                exit_code = 1;
+            }
+            Ok((level, Outcome::TimedOut(ticks))) => {
+                println!("{}: level {} failed, timed out after {} ticks", path.display(), level, ticks);
+                exit_code = 1;
+            }
+            Err(err) => {
+                println!("{}: error: {:#}", path.display(), err);
+                exit_code = 1;
+            }
+        }
+    }
+    exit_code
+}